@@ -0,0 +1,63 @@
+// Compares the two ways of grabbing a zoom-sized area of the primary display:
+// capturing the whole screen and cropping it, versus asking the display server
+// for just the region.
+//
+// Needs a running display. Run with: cargo bench --bench capture
+
+use screenshots::Screen;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 20;
+const AREA_SIZE: u32 = 200;
+
+fn main() {
+    let screen = match Screen::all().ok().and_then(|screens| screens.into_iter().next()) {
+        Some(screen) => screen,
+        None => {
+            eprintln!("No display available, skipping capture benchmark");
+            return;
+        }
+    };
+    
+    let info = screen.display_info;
+    let width = AREA_SIZE.min(info.width);
+    let height = AREA_SIZE.min(info.height);
+    let x = (info.width - width) / 2;
+    let y = (info.height - height) / 2;
+    
+    println!("Display {}x{}, capturing {}x{} at ({}, {}), {} iterations",
+             info.width, info.height, width, height, x, y, ITERATIONS);
+    
+    let full_then_crop = time(|| {
+        let image = screen.capture().expect("full screen capture failed");
+        let buffer = image::RgbaImage::from_raw(image.width(), image.height(), image.rgba().to_vec())
+            .expect("invalid image buffer");
+        image::imageops::crop_imm(&buffer, x, y, width, height).to_image()
+    });
+    
+    let region = time(|| {
+        let image = screen.capture_area(x as i32, y as i32, width, height)
+            .expect("region capture failed");
+        image::RgbaImage::from_raw(image.width(), image.height(), image.rgba().to_vec())
+            .expect("invalid image buffer")
+    });
+    
+    println!("full screen + crop: {:>8.2} ms/iter", as_millis(full_then_crop));
+    println!("region capture:     {:>8.2} ms/iter", as_millis(region));
+    println!("speedup:            {:>8.2}x", full_then_crop.as_secs_f64() / region.as_secs_f64());
+}
+
+fn time<T>(mut capture: impl FnMut() -> T) -> Duration {
+    // Warm up the connection before measuring
+    std::hint::black_box(capture());
+    
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        std::hint::black_box(capture());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -99,6 +99,33 @@ impl WindowController {
         }
         
         // If exact match fails, try to find a matching window
+        let (window_id, _) = Self::find_window(window_name)?;
+        Self::focus_window_by_id(&window_id)
+    }
+    
+    pub fn focus_window_by_id(window_id: &str) -> Result<()> {
+        let focus_output = Command::new("wmctrl")
+            .args(["-i", "-a", window_id])
+            .output()?;
+        
+        if !focus_output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Failed to focus window: {}",
+                String::from_utf8_lossy(&focus_output.stderr)
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    pub fn find_window(window_name: &str) -> Result<(String, String)> {
+        // Accept a raw window ID as printed by `focus --list`
+        if window_name.starts_with("0x") {
+            if let Some(window) = Self::list_windows()?.into_iter().find(|(id, _)| id == window_name) {
+                return Ok(window);
+            }
+        }
+        
         let windows = Self::list_windows()?;
         let mut matches = Vec::new();
         
@@ -173,22 +200,7 @@ impl WindowController {
                 "No window found matching '{}'. Use --list to see available windows.",
                 window_name
             )),
-            1 => {
-                // Found exactly one match, focus it by ID
-                let (window_id, _) = &matches[0];
-                let focus_output = Command::new("wmctrl")
-                    .args(&["-i", "-a", window_id])
-                    .output()?;
-                
-                if !focus_output.status.success() {
-                    return Err(anyhow::anyhow!(
-                        "Failed to focus window: {}",
-                        String::from_utf8_lossy(&focus_output.stderr)
-                    ));
-                }
-                
-                Ok(())
-            }
+            1 => Ok(matches.remove(0)),
             _ => {
                 // Multiple matches, show them to user
                 let mut error_msg = format!("Multiple windows match '{}'. Please be more specific:\n", window_name);
@@ -200,6 +212,87 @@ impl WindowController {
         }
     }
     
+    pub fn window_geometry(window_id: &str) -> Result<(i32, i32, u32, u32)> {
+        // `wmctrl -lG` prints: id desktop x y width height host title
+        let output = Command::new("wmctrl")
+            .args(["-l", "-G"])
+            .output()?;
+        
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Failed to list window geometry: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        
+        let window_list = String::from_utf8(output.stdout)?;
+        
+        for line in window_list.lines() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() >= 6 && parts[0] == window_id {
+                return Ok((
+                    parts[2].parse()?,
+                    parts[3].parse()?,
+                    parts[4].parse()?,
+                    parts[5].parse()?,
+                ));
+            }
+        }
+        
+        Err(anyhow::anyhow!("Window {} not found", window_id))
+    }
+    
+    /// Title of the window containing screen point (`x`, `y`). `wmctrl` doesn't
+    /// report stacking order, so when windows overlap the smallest one is taken,
+    /// which is usually the dialog or panel on top.
+    pub fn window_at(x: i32, y: i32) -> Result<Option<String>> {
+        let output = Command::new("wmctrl")
+            .args(["-l", "-G"])
+            .output()?;
+        
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Failed to list window geometry: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        
+        let window_list = String::from_utf8(output.stdout)?;
+        let mut best: Option<(u64, String)> = None;
+        
+        for line in window_list.lines() {
+            // id desktop x y width height host title
+            let mut rest = line;
+            let mut fields = Vec::new();
+            for _ in 0..7 {
+                let trimmed = rest.trim_start();
+                let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+                fields.push(&trimmed[..end]);
+                rest = &trimmed[end..];
+            }
+            let title = rest.trim().to_string();
+            
+            let geometry = (fields[2].parse::<i32>(), fields[3].parse::<i32>(), fields[4].parse::<u32>(), fields[5].parse::<u32>());
+            let (left, top, width, height) = match geometry {
+                (Ok(left), Ok(top), Ok(width), Ok(height)) => (left, top, width, height),
+                _ => continue,
+            };
+            
+            // Sticky windows (desktop -1) are the desktop background and panels
+            let inside = x >= left && y >= top && x < left + width as i32 && y < top + height as i32;
+            if !inside || fields[1] == "-1" || title.is_empty() {
+                continue;
+            }
+            
+            let area = width as u64 * height as u64;
+            if best.as_ref().is_none_or(|(best_area, _)| area < *best_area) {
+                best = Some((area, title));
+            }
+        }
+        
+        Ok(best.map(|(_, title)| title))
+    }
+    
     pub fn focus_and_maximize_window(window_name: &str) -> Result<()> {
         // First focus the window, then maximize it
         Self::focus_window(window_name)?;
@@ -0,0 +1,402 @@
+use anyhow::Result;
+use crate::config::Config;
+use crate::grid::{CapturedImage, SessionData};
+use crate::session::{ActionEntry, SessionManager};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+const BUNDLE_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+const SESSION_FILE: &str = "session.json";
+const CONFIG_FILE: &str = "config.toml";
+const ACTIONS_FILE: &str = "actions.jsonl";
+const SCREENSHOTS_DIR: &str = "screenshots";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub version: u32,
+    pub session_name: String,
+    pub exported_at: DateTime<Utc>,
+    pub tool_version: String,
+    pub screenshots: Vec<String>,        // Files under `screenshots/`
+    pub missing_screenshots: Vec<String>, // Referenced by the session but not found on export
+}
+
+/// A session export: everything needed to reproduce what an agent saw and did.
+///
+/// Inside the archive, image paths in `session.json` point at `screenshots/<file>`.
+pub struct SessionBundle {
+    pub manifest: BundleManifest,
+    pub session: SessionData,
+    pub config: String,
+    pub actions: Vec<ActionEntry>,
+    pub screenshots: BTreeMap<String, Vec<u8>>,
+}
+
+impl SessionBundle {
+    /// Collect the stored session `name` together with its screenshots,
+    /// the effective config and the actions run against the current grid.
+    pub fn from_session(config: &Config, name: &str) -> Result<Self> {
+        let mut session = SessionManager::read_session(name)?
+            .ok_or_else(|| anyhow::anyhow!("Session '{}' not found. See 'gui-helper session list'.", name))?;
+        
+        let actions = SessionManager::read_actions(name)?
+            .into_iter()
+            .filter(|entry| session.session_id.is_none() || entry.session_id == session.session_id)
+            .collect();
+        
+        // Clean copies are optional, so one pruned since is dropped rather than reported
+        for image in captured_images(&mut session) {
+            if image.clean_path.as_ref().is_some_and(|clean_path| !clean_path.exists()) {
+                image.clean_path = None;
+            }
+        }
+        
+        let mut screenshots = BTreeMap::new();
+        let mut missing_screenshots = Vec::new();
+        
+        for_each_image_path(&mut session, |path| {
+            let file_name = match path.file_name() {
+                Some(file_name) => file_name.to_string_lossy().to_string(),
+                None => return,
+            };
+            
+            if !screenshots.contains_key(&file_name) {
+                match fs::read(&*path) {
+                    Ok(bytes) => {
+                        screenshots.insert(file_name.clone(), bytes);
+                    }
+                    Err(_) => {
+                        if !missing_screenshots.contains(&file_name) {
+                            missing_screenshots.push(file_name.clone());
+                        }
+                        return;
+                    }
+                }
+            }
+            
+            *path = Path::new(SCREENSHOTS_DIR).join(&file_name);
+        });
+        
+        Ok(Self {
+            manifest: BundleManifest {
+                version: BUNDLE_VERSION,
+                session_name: name.to_string(),
+                exported_at: Utc::now(),
+                tool_version: env!("CARGO_PKG_VERSION").to_string(),
+                screenshots: screenshots.keys().cloned().collect(),
+                missing_screenshots,
+            },
+            session,
+            config: toml::to_string_pretty(config)?,
+            actions,
+            screenshots,
+        })
+    }
+    
+    /// Write the bundle as a gzip-compressed tar archive.
+    pub fn write(&self, output_path: &Path) -> Result<()> {
+        if let Some(parent) = output_path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        
+        let file = fs::File::create(output_path)?;
+        let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        
+        let mut actions = String::new();
+        for entry in &self.actions {
+            actions.push_str(&serde_json::to_string(entry)?);
+            actions.push('\n');
+        }
+        
+        Self::append(&mut archive, MANIFEST_FILE, serde_json::to_string_pretty(&self.manifest)?.as_bytes())?;
+        Self::append(&mut archive, SESSION_FILE, serde_json::to_string_pretty(&self.session)?.as_bytes())?;
+        Self::append(&mut archive, CONFIG_FILE, self.config.as_bytes())?;
+        Self::append(&mut archive, ACTIONS_FILE, actions.as_bytes())?;
+        for (file_name, bytes) in &self.screenshots {
+            Self::append(&mut archive, &format!("{}/{}", SCREENSHOTS_DIR, file_name), bytes)?;
+        }
+        
+        archive.into_inner()?.finish()?;
+        Ok(())
+    }
+    
+    /// Read a bundle written by `write`. Entries outside the known layout are ignored.
+    pub fn read(bundle_path: &Path) -> Result<Self> {
+        let file = fs::File::open(bundle_path)
+            .map_err(|e| anyhow::anyhow!("Failed to open bundle {:?}: {}", bundle_path, e))?;
+        let mut archive = tar::Archive::new(GzDecoder::new(file));
+        
+        let mut manifest = None;
+        let mut session = None;
+        let mut config = String::new();
+        let mut actions = Vec::new();
+        let mut screenshots = BTreeMap::new();
+        
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let entry_path = entry.path()?.into_owned();
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            
+            match entry_path.to_str() {
+                Some(MANIFEST_FILE) => manifest = Some(serde_json::from_slice::<BundleManifest>(&contents)?),
+                Some(SESSION_FILE) => session = Some(SessionManager::parse_session(&String::from_utf8_lossy(&contents))?),
+                Some(CONFIG_FILE) => config = String::from_utf8_lossy(&contents).to_string(),
+                Some(ACTIONS_FILE) => {
+                    actions = String::from_utf8_lossy(&contents)
+                        .lines()
+                        .filter_map(|line| serde_json::from_str(line).ok())
+                        .collect();
+                }
+                _ => {
+                    if let Some(file_name) = Self::screenshot_name(&entry_path) {
+                        screenshots.insert(file_name, contents);
+                    }
+                }
+            }
+        }
+        
+        let manifest = manifest.ok_or_else(|| anyhow::anyhow!("{:?} is not a session bundle: {} missing", bundle_path, MANIFEST_FILE))?;
+        if manifest.version > BUNDLE_VERSION {
+            return Err(anyhow::anyhow!(
+                "Bundle version {} is newer than supported version {}",
+                manifest.version, BUNDLE_VERSION
+            ));
+        }
+        
+        Ok(Self {
+            manifest,
+            session: session.ok_or_else(|| anyhow::anyhow!("{:?} is not a session bundle: {} missing", bundle_path, SESSION_FILE))?,
+            config,
+            actions,
+            screenshots,
+        })
+    }
+    
+    /// Restore the bundle as session `name`. Screenshots and the exported config
+    /// are unpacked to `<data dir>/imports/<name>/` and the session is pointed at them.
+    pub fn import(mut self, name: &str, force: bool) -> Result<PathBuf> {
+        if SessionManager::read_session(name)?.is_some() && !force {
+            return Err(anyhow::anyhow!(
+                "Session '{}' already exists. Use --as <NAME> or --force to replace it.",
+                name
+            ));
+        }
+        
+        let import_dir = Config::data_dir()?.join("imports").join(name);
+        if import_dir.exists() {
+            fs::remove_dir_all(&import_dir)?;
+        }
+        
+        let screenshots_dir = import_dir.join(SCREENSHOTS_DIR);
+        fs::create_dir_all(&screenshots_dir)?;
+        for (file_name, bytes) in &self.screenshots {
+            fs::write(screenshots_dir.join(file_name), bytes)?;
+        }
+        fs::write(import_dir.join(CONFIG_FILE), &self.config)?;
+        fs::write(import_dir.join(MANIFEST_FILE), serde_json::to_string_pretty(&self.manifest)?)?;
+        
+        for_each_image_path(&mut self.session, |path| {
+            if let Some(file_name) = Self::screenshot_name(path) {
+                *path = screenshots_dir.join(file_name);
+            }
+        });
+        // A clean copy not in the bundle would point into the exporter's file system
+        for image in captured_images(&mut self.session) {
+            if image.clean_path.as_ref().is_some_and(|clean_path| !clean_path.starts_with(&screenshots_dir)) {
+                image.clean_path = None;
+            }
+        }
+        
+        SessionManager::save_session(name, &self.session)?;
+        SessionManager::write_actions(name, &self.actions)?;
+        Ok(import_dir)
+    }
+    
+    fn append<W: std::io::Write>(archive: &mut tar::Builder<W>, path: &str, contents: &[u8]) -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(Utc::now().timestamp().max(0) as u64);
+        header.set_cksum();
+        archive.append_data(&mut header, path, contents)?;
+        Ok(())
+    }
+    
+    // Only `screenshots/<plain file name>` is accepted, so archives can't write elsewhere
+    fn screenshot_name(path: &Path) -> Option<String> {
+        let mut components = path.components();
+        match (components.next(), components.next(), components.next()) {
+            (Some(Component::Normal(dir)), Some(Component::Normal(file_name)), None) if dir == SCREENSHOTS_DIR => {
+                Some(file_name.to_string_lossy().to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Visit every screenshot path stored in the session and its history,
+/// including the clean copies kept for `template save`.
+fn for_each_image_path(session: &mut SessionData, mut visit: impl FnMut(&mut PathBuf)) {
+    for image in captured_images(session) {
+        for path in [&mut image.path, &mut image.clean_path].into_iter().flatten() {
+            visit(path);
+        }
+    }
+    
+    for grid_step in &mut session.history {
+        if let Some(path) = grid_step.screenshot.as_mut() {
+            visit(path);
+        }
+    }
+}
+
+// The overview and zoom images of the session and of every history step
+fn captured_images(session: &mut SessionData) -> impl Iterator<Item = &mut CapturedImage> {
+    session.overview_image.iter_mut()
+        .chain(session.zoom_image.iter_mut())
+        .chain(session.history.iter_mut().filter_map(|grid_step| grid_step.zoom_image.as_mut()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TEST_DATA_DIR;
+    use crate::grid::{GridGenerator, SESSION_VERSION};
+    
+    // A data directory of its own for the current test thread, removed on drop
+    struct TempDataDir(PathBuf);
+    
+    impl TempDataDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("gui-helper-bundle-{}-{}", std::process::id(), name));
+            fs::create_dir_all(&dir).unwrap();
+            TEST_DATA_DIR.with(|data_dir| *data_dir.borrow_mut() = Some(dir.clone()));
+            Self(dir)
+        }
+    }
+    
+    impl Drop for TempDataDir {
+        fn drop(&mut self) {
+            TEST_DATA_DIR.with(|data_dir| *data_dir.borrow_mut() = None);
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+    
+    #[test]
+    fn round_trips_a_session_with_its_screenshots() {
+        let data_dir = TempDataDir::new("round-trip");
+        let screenshots_dir = data_dir.0.join("shots");
+        fs::create_dir_all(&screenshots_dir).unwrap();
+        let overview_path = screenshots_dir.join("overview_1.png");
+        let clean_path = screenshots_dir.join("overview_1.clean.png");
+        fs::write(&overview_path, b"overview").unwrap();
+        fs::write(&clean_path, b"clean").unwrap();
+        
+        let (overview_grid, screen_info) = GridGenerator::generate_overview_grid(800, 600, 100);
+        let mut session = SessionData {
+            version: SESSION_VERSION,
+            overview_grid,
+            selected_square: None,
+            zoom_area: None,
+            zoom_grid: vec![],
+            screen_info,
+            timestamp: Utc::now(),
+            overview_image: Some(
+                CapturedImage::new(Some(overview_path.clone()), "png", 800, 600, 0, 0, 800)
+                    .with_grid_drawn(true)
+                    .with_clean_path(Some(clean_path)),
+            ),
+            zoom_image: None,
+            session_id: Some("20260102-030405-006".to_string()),
+            fingerprint: None,
+            history: vec![],
+            current_step: None,
+        };
+        session.record_step("overview");
+        SessionManager::save_session("exported", &session).unwrap();
+        let action = ActionEntry {
+            timestamp: Utc::now(),
+            session_id: session.session_id.clone(),
+            command: vec!["overview".to_string()],
+            error: None,
+        };
+        SessionManager::write_actions("exported", &[action]).unwrap();
+        
+        let bundle_path = data_dir.0.join("exported.tar.gz");
+        let bundle = SessionBundle::from_session(&Config::default(), "exported").unwrap();
+        assert_eq!(bundle.manifest.screenshots, ["overview_1.clean.png", "overview_1.png"]);
+        assert!(bundle.manifest.missing_screenshots.is_empty());
+        bundle.write(&bundle_path).unwrap();
+        
+        let bundle = SessionBundle::read(&bundle_path).unwrap();
+        assert_eq!(bundle.manifest.session_name, "exported");
+        assert_eq!(bundle.actions.len(), 1);
+        let import_dir = bundle.import("imported", false).unwrap();
+        
+        // Every path now points at the unpacked copy, none at the exporter's files
+        let imported = SessionManager::read_session("imported").unwrap().unwrap();
+        let image = imported.overview_image.unwrap();
+        let unpacked = import_dir.join(SCREENSHOTS_DIR);
+        assert_eq!(image.path.as_deref(), Some(unpacked.join("overview_1.png").as_path()));
+        assert_eq!(image.clean_path.as_deref(), Some(unpacked.join("overview_1.clean.png").as_path()));
+        assert_eq!(imported.history[0].screenshot.as_deref(), Some(unpacked.join("overview_1.png").as_path()));
+        assert_eq!(fs::read(unpacked.join("overview_1.png")).unwrap(), b"overview");
+        assert_eq!(fs::read(unpacked.join("overview_1.clean.png")).unwrap(), b"clean");
+        assert_eq!(SessionManager::read_actions("imported").unwrap().len(), 1);
+        
+        // Importing over an existing session needs --force
+        let bundle = SessionBundle::read(&bundle_path).unwrap();
+        assert!(bundle.import("imported", false).is_err());
+    }
+    
+    #[test]
+    fn missing_clean_copies_are_dropped_on_export() {
+        let data_dir = TempDataDir::new("missing-clean");
+        let overview_path = data_dir.0.join("overview_1.png");
+        fs::write(&overview_path, b"overview").unwrap();
+        
+        let (overview_grid, screen_info) = GridGenerator::generate_overview_grid(800, 600, 100);
+        let session = SessionData {
+            version: SESSION_VERSION,
+            overview_grid,
+            selected_square: None,
+            zoom_area: None,
+            zoom_grid: vec![],
+            screen_info,
+            timestamp: Utc::now(),
+            overview_image: Some(
+                CapturedImage::new(Some(overview_path), "png", 800, 600, 0, 0, 800)
+                    .with_clean_path(Some(data_dir.0.join("overview_1.clean.png"))),
+            ),
+            zoom_image: None,
+            session_id: None,
+            fingerprint: None,
+            history: vec![],
+            current_step: None,
+        };
+        SessionManager::save_session("exported", &session).unwrap();
+        
+        let bundle = SessionBundle::from_session(&Config::default(), "exported").unwrap();
+        assert_eq!(bundle.manifest.screenshots, ["overview_1.png"]);
+        assert!(bundle.manifest.missing_screenshots.is_empty());
+        assert!(bundle.session.overview_image.unwrap().clean_path.is_none());
+    }
+    
+    #[test]
+    fn screenshot_names_stay_inside_the_screenshots_directory() {
+        assert_eq!(SessionBundle::screenshot_name(Path::new("screenshots/a.png")).as_deref(), Some("a.png"));
+        
+        for path in ["../x", "/abs", "/screenshots/a.png", "screenshots/a/b", "screenshots/..", "screenshots/../x", "screenshots", "other/a.png"] {
+            assert_eq!(SessionBundle::screenshot_name(Path::new(path)), None, "{}", path);
+        }
+    }
+}
@@ -1,17 +1,48 @@
 use anyhow::Result;
-use crate::config::Config;
-use crate::grid::GridGenerator;
+use crate::config::{Config, StaleScreenPolicy};
+use crate::grid::{GridGenerator, SessionData};
 use crate::automation::MouseController;
+use crate::commands::ocr;
+use crate::overlay::{font, Color, GridRenderer, Placement};
+use crate::screenshot::{
+    hash_distance, heatmap, perceptual_hash, CaptureKind, DisplayGeometry, GridParameters, ImageDiff,
+    ScreenFingerprint, ScreenshotCapture, ScreenshotRecord, ScreenshotStore,
+};
 use crate::session::SessionManager;
+use chrono::Utc;
+use image::RgbaImage;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
 
+/// Screen pixels captured around the target for receipts and `--verify`.
+const RECEIPT_SIZE: u32 = 200;
+
+// What a click aims at, for the receipt and `--verify`
+struct Target {
+    x: u32,
+    y: u32,
+    label: String,                // Receipt caption, e.g. "B5/23"
+    file_label: String,           // Part of saved filenames, e.g. "B5_23"
+    context: Option<String>,      // Printed after the click
+    screen: (u32, u32),           // Screen size, to keep captures on screen
+    grid: Option<GridParameters>, // Grid recorded with saved captures
+    session_id: Option<String>,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
     config: &Config,
+    session: &str,
     zoom_square_id: u32,
     delay_override: Option<u64>,
     double_click: bool,
+    force: bool,
+    verify: bool,
+    expect_change: bool,
 ) -> Result<()> {
     // Load session data
-    let session_data = SessionManager::load_session()?
+    let session_data = SessionManager::load_session(config, session)?
         .ok_or_else(|| anyhow::anyhow!("No zoom session found. Run 'gui-helper zoom' first."))?;
     
     // Check if we have zoom data
@@ -27,10 +58,118 @@ pub fn execute(
     println!("Clicking zoom square {} at ({}, {})", 
              zoom_square.id, zoom_square.center_x, zoom_square.center_y);
     
+    check_screen(config, &session_data, force)?;
+    
+    let parent_square = session_data.zoom_area.as_ref()
+        .map(|zoom_area| zoom_area.parent_square.clone())
+        .unwrap_or_default();
+    let target = Target {
+        x: zoom_square.center_x,
+        y: zoom_square.center_y,
+        label: format!("{}/{}", parent_square, zoom_square.id),
+        file_label: format!("{}_{}", parent_square, zoom_square.id),
+        context: Some(format!("Clicked in zoom area of square '{}' at local position ({}, {})",
+                              parent_square, zoom_square.local_x, zoom_square.local_y)),
+        screen: (session_data.screen_info.width, session_data.screen_info.height),
+        grid: session_data.zoom_area.clone().map(GridParameters::Zoom),
+        session_id: session_data.session_id.clone(),
+    };
+    
+    perform(config, &target, delay_override, double_click, verify, expect_change)
+}
+
+/// Click the one place where OCR finds `text` on screen (or in `region`).
+/// Fails without clicking when the text is missing or appears more than once.
+#[allow(clippy::too_many_arguments)]
+pub fn text(
+    config: &Config,
+    session: &str,
+    text: &str,
+    region: Option<&str>,
+    delay_override: Option<u64>,
+    double_click: bool,
+    verify: bool,
+    expect_change: bool,
+) -> Result<()> {
+    println!("Looking for {:?} on screen...", text);
+    let mut found = ocr::locate(config, session, text, region)?;
+    
+    let found = match found.len() {
+        0 => return Err(anyhow::anyhow!(
+            "No text matching {:?} found (minimum confidence {}). Run 'gui-helper ocr' to see what was recognized",
+            text, config.ocr.min_confidence
+        )),
+        1 => found.remove(0),
+        count => {
+            let places = found.iter()
+                .map(|found| format!("  {:?} at ({}, {}) {}", found.text, found.center_x, found.center_y,
+                                     found.address().unwrap_or_default()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            return Err(anyhow::anyhow!(
+                "{:?} appears {} times; narrow it down with --region or click a zoom square instead:\n{}",
+                text, count, places
+            ));
+        }
+    };
+    
+    println!("Clicking {:?} at ({}, {}) ({:.0}% confidence)", found.text, found.center_x, found.center_y, found.confidence);
+    
+    let session_data = SessionManager::load_session(config, session)?;
+    let screen = match &session_data {
+        Some(session_data) => (session_data.screen_info.width, session_data.screen_info.height),
+        None => ScreenshotCapture::get_primary_display_size()?,
+    };
+    let target = Target {
+        x: found.center_x,
+        y: found.center_y,
+        // The receipt font has no quotes
+        label: match found.address() {
+            Some(address) => format!("{} {}", found.text, address),
+            None => found.text.clone(),
+        },
+        file_label: match (&found.square, found.zoom_square) {
+            (Some(square), Some(zoom_square)) => format!("{}_{}", square, zoom_square),
+            _ => "text".to_string(),
+        },
+        context: found.address().map(|address| format!("Text {:?} is in {}", found.text, address)),
+        screen,
+        grid: None,
+        session_id: session_data.and_then(|session_data| session_data.session_id),
+    };
+    
+    perform(config, &target, delay_override, double_click, verify, expect_change)
+}
+
+// Save the receipt, click, and verify the result when asked to
+fn perform(
+    config: &Config,
+    target: &Target,
+    delay_override: Option<u64>,
+    double_click: bool,
+    verify: bool,
+    expect_change: bool,
+) -> Result<()> {
+    // Record the target before clicking changes what's under it. A receipt
+    // that can't be written shouldn't stop the click.
+    if config.display.save_screenshots && config.display.click_receipts {
+        match save_receipt(config, target) {
+            Ok(receipt_path) => println!("Click receipt saved: {:?}", receipt_path),
+            Err(e) => eprintln!("⚠ Could not save click receipt: {}", e),
+        }
+    }
+    
     // Use override or config values
     let delay = delay_override.unwrap_or(config.automation.click_delay);
     let should_double_click = double_click || config.automation.double_click;
     
+    let (area_x, area_y, area_width, area_height) = target_area(target);
+    let before = if verify {
+        Some(ScreenshotCapture::capture_area(area_x, area_y, area_width, area_height)?)
+    } else {
+        None
+    };
+    
     // Create mouse controller and perform click
     let mut mouse = MouseController::new()?;
     
@@ -38,20 +177,171 @@ pub fn execute(
              if should_double_click { "double" } else { "single" }, delay);
     
     mouse.click(
-        zoom_square.center_x,
-        zoom_square.center_y,
+        target.x,
+        target.y,
         delay,
         should_double_click,
     )?;
     
     println!("Click executed successfully at pixel coordinates ({}, {})", 
-             zoom_square.center_x, zoom_square.center_y);
+             target.x, target.y);
     
     // Show some context about the click
-    if let Some(zoom_area) = &session_data.zoom_area {
-        println!("Context: Clicked in zoom area of square '{}' at local position ({}, {})", 
-                 zoom_area.parent_square, zoom_square.local_x, zoom_square.local_y);
+    if let Some(context) = &target.context {
+        println!("Context: {}", context);
+    }
+    
+    if let Some(before) = before {
+        thread::sleep(Duration::from_millis(config.automation.verify_delay));
+        let after = ScreenshotCapture::capture_area(area_x, area_y, area_width, area_height)?;
+        let changed = verify_click(config, target, &before, &after)?;
+        if expect_change && !changed {
+            return Err(anyhow::anyhow!(
+                "No change detected around the click target after {} ms (--expect-change)",
+                config.automation.verify_delay
+            ));
+        }
+    }
+    
+    Ok(())
+}
+
+// Screen area around the click target, kept on screen; near the edges the
+// target moves off center
+fn target_area(target: &Target) -> (u32, u32, u32, u32) {
+    let (screen_width, screen_height) = target.screen;
+    let width = RECEIPT_SIZE.min(screen_width);
+    let height = RECEIPT_SIZE.min(screen_height);
+    let x = target.x.saturating_sub(width / 2).min(screen_width - width);
+    let y = target.y.saturating_sub(height / 2).min(screen_height - height);
+    (x, y, width, height)
+}
+
+// Report how much the area around the target changed and save the heatmap.
+// Returns whether the change reached `automation.change_threshold`.
+fn verify_click(config: &Config, target: &Target, before: &RgbaImage, after: &RgbaImage) -> Result<bool> {
+    let diff = ImageDiff::compute(before, after)?;
+    let changed = diff.changed(config.automation.change_threshold);
+    
+    println!("Verify: {:.2}% of the {}x{} area around the target changed ({} of {} pixels)",
+             diff.score * 100.0, after.width(), after.height(), diff.changed_pixels, diff.total_pixels);
+    if let Some((x, y, width, height)) = diff.bounds {
+        let (area_x, area_y, _, _) = target_area(target);
+        println!("   Changed region: {}x{} at ({}, {})", width, height, area_x + x, area_y + y);
+    }
+    if changed {
+        println!("✓ The screen changed after the click");
+    } else {
+        println!("⚠ Nothing changed around the target (threshold {:.2}%)",
+                 config.automation.change_threshold * 100.0);
+    }
+    
+    if config.display.save_screenshots {
+        let heatmap_path = save_capture(config, target, "diff", CaptureKind::Diff, &heatmap(before, after)?)?;
+        println!("Diff heatmap saved: {:?}", heatmap_path);
+    }
+    
+    Ok(changed)
+}
+
+// Crop around the click target with a crosshair on it, its label and the
+// time, saved next to the other screenshots.
+fn save_receipt(config: &Config, target: &Target) -> Result<PathBuf> {
+    let (x, y, width, height) = target_area(target);
+    
+    let mut image = ScreenshotCapture::capture_area(x, y, width, height)?;
+    let placement = Placement::new(&image, x, y, width);
+    GridRenderer::draw_crosshair(&mut image, &placement, target.x, target.y, Color::rgb(255, 0, 0));
+    
+    let caption = [
+        format!("{} ({}, {})", target.label, target.x, target.y),
+        Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+    ];
+    GridRenderer::draw_caption(&mut image, &caption, font::scale_for(config.zoom_grid.font_size));
+    
+    save_capture(config, target, "click", CaptureKind::Receipt, &image)
+}
+
+// Save `image` as `<prefix>_<B5>_<23>_<time>` and add it to the index
+fn save_capture(config: &Config, target: &Target, prefix: &str, kind: CaptureKind, image: &RgbaImage) -> Result<PathBuf> {
+    let format = config.display.output_format.to_lowercase();
+    let screenshots_dir = SessionManager::get_screenshots_dir(config)?;
+    let output_path = SessionManager::write_screenshot(
+        &screenshots_dir,
+        None,
+        &format!("{}_{}", prefix, target.file_label),
+        &format,
+        &ScreenshotCapture::encode_image(image, &format, None)?,
+    )?;
+    
+    let mut record = ScreenshotRecord::new(&output_path, kind, target.session_id.clone())?;
+    if let Some(grid) = &target.grid {
+        record = record.with_grid(grid.clone());
     }
+    ScreenshotStore::record(config, record)?;
     
+    Ok(output_path)
+}
+
+/// Make sure the screen still looks like it did when the grid was captured,
+/// following `session.stale_screen`. `--force` turns a refusal into a warning.
+fn check_screen(config: &Config, session_data: &SessionData, force: bool) -> Result<()> {
+    let policy = config.session.stale_screen;
+    if policy == StaleScreenPolicy::Ignore {
+        return Ok(());
+    }
+    
+    // Sessions written before fingerprints existed can't be checked
+    let fingerprint = match &session_data.fingerprint {
+        Some(fingerprint) => fingerprint,
+        None => return Ok(()),
+    };
+    
+    let reason = match stale_reason(config, session_data, fingerprint)? {
+        Some(reason) => reason,
+        None => return Ok(()),
+    };
+    
+    if policy == StaleScreenPolicy::Refuse && !force {
+        return Err(anyhow::anyhow!(
+            "{}. Run 'gui-helper overview' again, or pass --force to click anyway.",
+            reason
+        ));
+    }
+    
+    eprintln!("⚠ {}. Clicking anyway.", reason);
     Ok(())
+}
+
+fn stale_reason(config: &Config, session_data: &SessionData, fingerprint: &ScreenFingerprint) -> Result<Option<String>> {
+    let displays = ScreenFingerprint::current_displays()?;
+    if displays != fingerprint.displays {
+        let describe = |displays: &[DisplayGeometry]| displays.iter()
+            .map(|display| format!("{}x{}+{}+{}", display.width, display.height, display.x, display.y))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Ok(Some(format!(
+            "Display layout changed since the grid was captured (was {}, now {})",
+            describe(&fingerprint.displays), describe(&displays)
+        )));
+    }
+    
+    // Compare the zoom area when there is one: that's where the click lands
+    let (stored_hash, current_hash) = match (fingerprint.zoom_hash, &session_data.zoom_area) {
+        (Some(zoom_hash), Some(zoom_area)) => {
+            let image = ScreenshotCapture::capture_area(zoom_area.x, zoom_area.y, zoom_area.width, zoom_area.height)?;
+            (zoom_hash, perceptual_hash(&image))
+        }
+        _ => (fingerprint.overview_hash, perceptual_hash(&ScreenshotCapture::capture_screen()?)),
+    };
+    
+    let distance = hash_distance(stored_hash, current_hash);
+    if distance > config.session.max_hash_distance {
+        return Ok(Some(format!(
+            "Screen content changed since the grid was captured (hash distance {}/64, limit {})",
+            distance, config.session.max_hash_distance
+        )));
+    }
+    
+    Ok(None)
 }
\ No newline at end of file
@@ -1,7 +1,12 @@
 use anyhow::Result;
-use crate::config::Config;
+use crate::config::layers;
+use crate::config::{Config, ConfigOrigin, LayeredConfig};
+use crate::screenshot::ScreenshotCapture;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
 
-pub fn execute(config: &Config, show: bool, reset: bool) -> Result<()> {
+pub fn execute(layered: &LayeredConfig, show: bool, reset: bool) -> Result<()> {
     if reset {
         let default_config = Config::default();
         default_config.save()?;
@@ -10,24 +15,138 @@ pub fn execute(config: &Config, show: bool, reset: bool) -> Result<()> {
     }
     
     if show {
-        let config_toml = toml::to_string_pretty(config)?;
+        return self::show(layered, false);
+    }
+    
+    // If neither show nor reset, display help
+    println!("Config command options:");
+    println!("  --show                     Show current configuration");
+    println!("  --reset                    Reset configuration to defaults");
+    println!("  show [--origin]            Show the effective configuration");
+    println!("  get <KEY>                  Print one value, e.g. zoom_grid.subdivision");
+    println!("  set <KEY> <VALUE>          Change a value in the user config file");
+    println!("  edit                       Open the user config file in $EDITOR");
+    println!("  validate                   Check every value and report the ones to fix");
+    
+    Ok(())
+}
+
+pub fn show(layered: &LayeredConfig, origin: bool) -> Result<()> {
+    if origin {
+        println!("Effective configuration (value  # layer):");
+        for (key, value, origin) in layered.entries()? {
+            println!("{} = {}  # {}", key, layers::display_value(&value), origin);
+        }
+    } else {
+        let config_toml = toml::to_string_pretty(&layered.config)?;
         println!("Current configuration:");
         println!("=====================");
         println!("{}", config_toml);
-        
-        let config_path = dirs::config_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
-            .join("gui-helper")
-            .join("config.toml");
-            
-        println!("\nConfiguration file location: {:?}", config_path);
+    }
+    
+    println!("\nConfiguration file location: {:?}", layered.user_path);
+    if let Some(project_path) = &layered.project_path {
+        println!("Project configuration: {:?}", project_path);
+    }
+    Ok(())
+}
+
+pub fn get(layered: &LayeredConfig, key: &str) -> Result<()> {
+    match layered.get(key)? {
+        toml::Value::String(value) => println!("{}", value),
+        toml::Value::Table(table) => print!("{}", toml::to_string_pretty(&table)?),
+        value => println!("{}", layers::display_value(&value)),
+    }
+    Ok(())
+}
+
+pub fn set(key: &str, value: &str, project: bool) -> Result<()> {
+    let path = target_file(project)?;
+    let value = layers::set_in_file(&path, key, value)?;
+    println!("Set {} = {} in {:?}", key, layers::display_value(&value), path);
+    
+    // A higher layer still wins over the file that was just changed
+    let layered = LayeredConfig::load(&[])?;
+    if let Some(origin) = layered.origins.get(key) {
+        let overridden = match origin {
+            ConfigOrigin::User(origin_path) | ConfigOrigin::Project(origin_path) => origin_path != &path,
+            ConfigOrigin::Env(_) => true,
+            _ => false,
+        };
+        if overridden {
+            eprintln!("⚠ {} is still overridden by {}", key, origin);
+        }
+    }
+    
+    Ok(())
+}
+
+pub fn validate(layered: &LayeredConfig) -> Result<()> {
+    let mut issues = layered.issues();
+    // Without a display (e.g. over SSH) only the display-independent checks run
+    if let Ok((screen_width, screen_height)) = ScreenshotCapture::get_primary_display_size() {
+        issues.extend(layered.display_issues(screen_width, screen_height));
+    }
+    if issues.is_empty() {
+        println!("✓ Configuration is valid");
         return Ok(());
     }
     
-    // If neither show nor reset, display help
-    println!("Config command options:");
-    println!("  --show   Show current configuration");
-    println!("  --reset  Reset configuration to defaults");
+    for (issue, origin) in &issues {
+        println!("✗ {}", issue.key);
+        println!("    value:   {}", issue.value);
+        println!("    allowed: {}", issue.allowed);
+        println!("    set in:  {}", origin);
+    }
+    Err(anyhow::anyhow!("{} invalid config value(s)", issues.len()))
+}
+
+/// Edit a copy of the config file and only replace the original if it still loads.
+pub fn edit(project: bool) -> Result<()> {
+    let path = target_file(project)?;
+    let edit_path = path.with_extension("toml.edit");
     
+    if path.exists() {
+        fs::copy(&path, &edit_path)?;
+    } else {
+        fs::write(&edit_path, "")?;
+    }
+    
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Editors are often set with arguments, e.g. "code --wait"
+    let (program, args) = match shlex::split(&editor).as_deref() {
+        Some([program, args @ ..]) => (program.clone(), args.to_vec()),
+        _ => return Err(anyhow::anyhow!("Invalid editor command '{}' in $VISUAL or $EDITOR", editor)),
+    };
+    let status = Command::new(&program)
+        .args(&args)
+        .arg(&edit_path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to start editor '{}': {}", editor, e))?;
+    
+    if !status.success() {
+        return Err(anyhow::anyhow!("Editor exited with {}; {:?} left unchanged", status, path));
+    }
+    
+    let contents = fs::read_to_string(&edit_path)?;
+    if let Err(e) = layers::check_file(&contents) {
+        return Err(anyhow::anyhow!(
+            "{}\nYour changes are in {:?}; {:?} was left unchanged",
+            e, edit_path, path
+        ));
+    }
+    
+    fs::rename(&edit_path, &path)?;
+    println!("Saved {:?}", path);
     Ok(())
-}
\ No newline at end of file
+}
+
+fn target_file(project: bool) -> Result<PathBuf> {
+    if project {
+        layers::project_file_for_writing()
+    } else {
+        Config::config_path()
+    }
+}
@@ -0,0 +1,159 @@
+use anyhow::Result;
+use crate::commands::screenshot::parse_region;
+use crate::config::Config;
+use crate::grid::{GridGenerator, OverviewSquare, ScreenInfo};
+use crate::screenshot::{find_template, ScreenshotCapture, TemplateLibrary};
+use crate::session::SessionManager;
+use serde::Serialize;
+use std::path::Path;
+
+/// A match in screen coordinates, with the grid addresses that reach it.
+#[derive(Debug, Serialize)]
+pub struct FoundMatch {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub center_x: u32,
+    pub center_y: u32,
+    pub score: f64,
+    pub scale: f64,
+    pub square: Option<String>,   // Overview square containing the center
+    pub zoom_square: Option<u32>, // Zoom cell of that square containing the center
+}
+
+pub fn execute(
+    config: &Config,
+    session: &str,
+    template_path: &Path,
+    threshold: f64,
+    region: Option<&str>,
+    all: bool,
+    json: bool,
+) -> Result<()> {
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(anyhow::anyhow!("Invalid threshold {}. Expected a value between 0.0 and 1.0", threshold));
+    }
+    
+    // A bare name that isn't a file refers to the template library
+    let library_path;
+    let template_path = match template_path.to_str() {
+        Some(name) if !template_path.exists() && TemplateLibrary::validate_name(name).is_ok() => {
+            library_path = TemplateLibrary::image_path(name)?;
+            if !library_path.exists() {
+                return Err(anyhow::anyhow!(
+                    "No file {:?} and no saved template '{}'. See 'gui-helper template list'",
+                    template_path, name
+                ));
+            }
+            library_path.as_path()
+        }
+        _ => template_path,
+    };
+    
+    let template = image::open(template_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read template {:?}: {}", template_path, e))?
+        .to_rgba8();
+    
+    let (screen_width, screen_height) = ScreenshotCapture::get_primary_display_size()?;
+    let (area_x, area_y, area_width, image) = match region.map(parse_region).transpose()? {
+        Some((x, y, width, height)) => (x, y, width, ScreenshotCapture::capture_area(x, y, width, height)?),
+        None => (0, 0, screen_width, ScreenshotCapture::capture_screen()?),
+    };
+    
+    // Captures can be scaled relative to screen coordinates (HiDPI)
+    let to_screen = area_width as f64 / image.width() as f64;
+    let (overview_grid, screen_info) = overview_grid(config, session, screen_width, screen_height)?;
+    
+    let mut found: Vec<FoundMatch> = find_template(&image, &template, threshold, all)?
+        .into_iter()
+        .map(|found| {
+            let x = area_x + (found.x as f64 * to_screen).round() as u32;
+            let y = area_y + (found.y as f64 * to_screen).round() as u32;
+            let width = ((found.width as f64 * to_screen).round() as u32).max(1);
+            let height = ((found.height as f64 * to_screen).round() as u32).max(1);
+            let (center_x, center_y) = (x + width / 2, y + height / 2);
+            
+            let (square, zoom_square) = grid_address(config, &overview_grid, &screen_info, center_x, center_y);
+            
+            FoundMatch {
+                x,
+                y,
+                width,
+                height,
+                center_x,
+                center_y,
+                score: found.score,
+                scale: found.scale,
+                square,
+                zoom_square,
+            }
+        })
+        .collect();
+    if !all {
+        found.truncate(1);
+    }
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&found)?);
+    } else {
+        print_matches(&found, template_path);
+    }
+    
+    if found.is_empty() {
+        return Err(anyhow::anyhow!("No match for {:?} with a score of at least {:.2}", template_path, threshold));
+    }
+    Ok(())
+}
+
+/// The grid `zoom` would use: the session's, unless it expired, or one built from config.
+pub fn overview_grid(config: &Config, session: &str, screen_width: u32, screen_height: u32) -> Result<(Vec<OverviewSquare>, ScreenInfo)> {
+    match SessionManager::load_session(config, session)? {
+        Some(session_data) => Ok((session_data.overview_grid, session_data.screen_info)),
+        None => Ok(GridGenerator::generate_overview_grid(screen_width, screen_height, config.overview_grid.square_size)),
+    }
+}
+
+/// Overview square and zoom cell containing a screen point, the zoom cell being
+/// the one `zoom` would produce with the current config.
+pub fn grid_address(
+    config: &Config,
+    overview_grid: &[OverviewSquare],
+    screen_info: &ScreenInfo,
+    x: u32,
+    y: u32,
+) -> (Option<String>, Option<u32>) {
+    let square = match GridGenerator::find_square_at(overview_grid, x, y) {
+        Some(square) => square,
+        None => return (None, None),
+    };
+    let (_, zoom_squares) = GridGenerator::generate_zoom_grid(
+        square,
+        config.zoom_grid.padding,
+        config.zoom_grid.subdivision,
+        screen_info,
+    );
+    let zoom_square = GridGenerator::find_zoom_square_at(&zoom_squares, x, y).map(|zoom_square| zoom_square.id);
+    (Some(square.id.clone()), zoom_square)
+}
+
+fn print_matches(found: &[FoundMatch], template_path: &Path) {
+    if found.is_empty() {
+        return;
+    }
+    
+    println!("Found {} match(es) for {:?}:", found.len(), template_path);
+    for (index, found) in found.iter().enumerate() {
+        println!("  {}. {}x{} at ({}, {}), center ({}, {})  score {:.3}  scale {:.2}",
+                 index + 1, found.width, found.height, found.x, found.y,
+                 found.center_x, found.center_y, found.score, found.scale);
+        match (&found.square, found.zoom_square) {
+            (Some(square), Some(zoom_square)) => println!(
+                "     {}/{}: gui-helper zoom --square {} && gui-helper click --zoom {}",
+                square, zoom_square, square, zoom_square
+            ),
+            (Some(square), None) => println!("     Square {}", square),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::Result;
+use crate::config::Config;
+use crate::grid::GridGenerator;
+use crate::session::SessionManager;
+
+pub fn execute(config: &Config, session: &str, from_image_px: &str, zoom: bool) -> Result<()> {
+    let session_data = SessionManager::load_session(config, session)?
+        .ok_or_else(|| anyhow::anyhow!("No session found. Run 'gui-helper overview' first."))?;
+    
+    let (image_x, image_y) = parse_point(from_image_px)?;
+    
+    let captured_image = if zoom {
+        session_data.zoom_image.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No zoom image in session. Run 'gui-helper zoom --square <ID>' first."))?
+    } else {
+        session_data.overview_image.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No overview image in session. Run 'gui-helper overview' first."))?
+    };
+    
+    if image_x < 0.0 || image_y < 0.0 || image_x >= captured_image.width as f64 || image_y >= captured_image.height as f64 {
+        return Err(anyhow::anyhow!(
+            "Position ({}, {}) is outside the {}x{} image",
+            image_x, image_y, captured_image.width, captured_image.height
+        ));
+    }
+    
+    let (screen_x, screen_y) = captured_image.to_screen(image_x, image_y);
+    
+    println!("Image position ({}, {}) at scale {:.3} maps to screen coordinates ({}, {})",
+             image_x, image_y, captured_image.scale, screen_x, screen_y);
+    
+    if let Some(square) = GridGenerator::find_square_at(&session_data.overview_grid, screen_x, screen_y) {
+        println!("  Overview square: {}", square.id);
+    }
+    
+    if let Some(zoom_square) = GridGenerator::find_zoom_square_at(&session_data.zoom_grid, screen_x, screen_y) {
+        println!("  Zoom square: {} (use 'gui-helper click --zoom {}')", zoom_square.id, zoom_square.id);
+    }
+    
+    Ok(())
+}
+
+fn parse_point(point: &str) -> Result<(f64, f64)> {
+    let parts: Vec<&str> = point.split(',').map(|part| part.trim()).collect();
+    
+    match parts[..] {
+        [x, y] => Ok((
+            x.parse().map_err(|_| anyhow::anyhow!("Invalid x coordinate '{}'", x))?,
+            y.parse().map_err(|_| anyhow::anyhow!("Invalid y coordinate '{}'", y))?,
+        )),
+        _ => Err(anyhow::anyhow!("Invalid position '{}'. Expected x,y", point)),
+    }
+}
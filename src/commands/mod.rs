@@ -7,4 +7,10 @@ pub mod open;
 pub mod maximize;
 pub mod unmaximize;
 pub mod focus;
-pub mod window;
\ No newline at end of file
+pub mod window;
+pub mod screenshot;
+pub mod locate;
+pub mod screenshots;
+pub mod wait;
+pub mod find;pub mod template;
+pub mod ocr;
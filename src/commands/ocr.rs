@@ -0,0 +1,122 @@
+use anyhow::Result;
+use crate::commands::find::{grid_address, overview_grid};
+use crate::commands::screenshot::{parse_region, resolve_square};
+use crate::config::Config;
+use crate::screenshot::{find_phrase, recognize, OcrWord, ScreenshotCapture};
+use serde::Serialize;
+
+/// Recognized text in screen coordinates, with the grid addresses that reach it.
+#[derive(Debug, Serialize)]
+pub struct ScreenText {
+    pub text: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub center_x: u32,
+    pub center_y: u32,
+    pub confidence: f32,
+    pub line: usize,              // Same number, same text line
+    pub square: Option<String>,   // Overview square containing the center
+    pub zoom_square: Option<u32>, // Zoom cell of that square containing the center
+}
+
+impl ScreenText {
+    /// "B5/23", or just the square when the zoom cell is unknown.
+    pub fn address(&self) -> Option<String> {
+        match (&self.square, self.zoom_square) {
+            (Some(square), Some(zoom_square)) => Some(format!("{}/{}", square, zoom_square)),
+            (Some(square), None) => Some(square.clone()),
+            _ => None,
+        }
+    }
+}
+
+pub fn execute(config: &Config, session: &str, region: Option<&str>, square: Option<&str>, json: bool) -> Result<()> {
+    let area = match (square, region) {
+        (Some(square_id), _) => Some(resolve_square(config, session, square_id)?),
+        (None, Some(region)) => Some(parse_region(region)?),
+        (None, None) => None,
+    };
+    
+    let (words, origin) = read_area(config, area)?;
+    let words = place(config, session, words, origin)?;
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&words)?);
+        return Ok(());
+    }
+    
+    if words.is_empty() {
+        println!("No text recognized (minimum confidence {})", config.ocr.min_confidence);
+        return Ok(());
+    }
+    
+    let mut line = None;
+    for word in &words {
+        if line != Some(word.line) {
+            println!("Line {}:", word.line + 1);
+            line = Some(word.line);
+        }
+        println!("  {:<24} {}x{} at ({}, {})  conf {:.0}  {}",
+                 format!("{:?}", word.text), word.width, word.height, word.x, word.y,
+                 word.confidence, word.address().unwrap_or_default());
+    }
+    println!("{} word(s) recognized", words.len());
+    Ok(())
+}
+
+/// Every place `text` appears on screen (or in `region`), in reading order.
+/// See `screenshot::find_phrase` for how words are compared.
+pub fn locate(config: &Config, session: &str, text: &str, region: Option<&str>) -> Result<Vec<ScreenText>> {
+    let area = region.map(parse_region).transpose()?;
+    let (words, origin) = read_area(config, area)?;
+    place(config, session, find_phrase(&words, text), origin)
+}
+
+// Capture the area (or the screen) and recognize it. Also returns the screen
+// position of the capture and the screen pixels per image pixel.
+fn read_area(config: &Config, area: Option<(u32, u32, u32, u32)>) -> Result<(Vec<OcrWord>, (u32, u32, f64))> {
+    let (area_x, area_y, area_width, image) = match area {
+        Some((x, y, width, height)) => (x, y, width, ScreenshotCapture::capture_area(x, y, width, height)?),
+        None => {
+            let (screen_width, _) = ScreenshotCapture::get_primary_display_size()?;
+            (0, 0, screen_width, ScreenshotCapture::capture_screen()?)
+        }
+    };
+    
+    // Captures can be scaled relative to screen coordinates (HiDPI)
+    let to_screen = area_width as f64 / image.width() as f64;
+    Ok((recognize(&image, &config.ocr)?, (area_x, area_y, to_screen)))
+}
+
+fn place(config: &Config, session: &str, words: Vec<OcrWord>, origin: (u32, u32, f64)) -> Result<Vec<ScreenText>> {
+    let (area_x, area_y, to_screen) = origin;
+    let (screen_width, screen_height) = ScreenshotCapture::get_primary_display_size()?;
+    let (overview_grid, screen_info) = overview_grid(config, session, screen_width, screen_height)?;
+    
+    Ok(words.into_iter()
+        .map(|word| {
+            let x = area_x + (word.x as f64 * to_screen).round() as u32;
+            let y = area_y + (word.y as f64 * to_screen).round() as u32;
+            let width = ((word.width as f64 * to_screen).round() as u32).max(1);
+            let height = ((word.height as f64 * to_screen).round() as u32).max(1);
+            let (center_x, center_y) = (x + width / 2, y + height / 2);
+            let (square, zoom_square) = grid_address(config, &overview_grid, &screen_info, center_x, center_y);
+            
+            ScreenText {
+                text: word.text,
+                x,
+                y,
+                width,
+                height,
+                center_x,
+                center_y,
+                confidence: word.confidence,
+                line: word.line,
+                square,
+                zoom_square,
+            }
+        })
+        .collect())
+}
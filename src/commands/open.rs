@@ -1,12 +1,13 @@
 use anyhow::Result;
+use crate::config::Config;
 use crate::session::SessionManager;
 use std::process::Command;
 use std::path::Path;
 
-pub fn execute(latest: bool, path: Option<String>) -> Result<()> {
+pub fn execute(config: &Config, latest: bool, path: Option<String>) -> Result<()> {
     let screenshot_path = if latest {
         // Get the latest screenshot
-        match SessionManager::get_latest_screenshot()? {
+        match SessionManager::get_latest_screenshot(config)? {
             Some(path) => path,
             None => {
                 println!("No screenshots found in the screenshots directory");
@@ -42,7 +43,7 @@ pub fn execute(latest: bool, path: Option<String>) -> Result<()> {
         let result = Command::new(viewer)
             .arg(&screenshot_path)
             .spawn();
-            
+        
         match result {
             Ok(mut child) => {
                 // For some viewers (like xdg-open), we don't need to wait
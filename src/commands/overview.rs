@@ -1,19 +1,38 @@
 use anyhow::Result;
 use crate::config::Config;
-use crate::grid::{GridGenerator, SessionData};
-use crate::overlay::SimpleOverlay;
-use crate::screenshot::ScreenshotCapture;
+use crate::emit::{EmitDocument, EmitFormat};
+use crate::grid::{CapturedImage, GridGenerator, SessionData, SESSION_VERSION};
+use crate::overlay::{GridRenderer, GridStyle, Placement, SimpleOverlay};
+use crate::screenshot::{wait_stable, CaptureKind, GridParameters, ImageBudget, ScreenFingerprint, ScreenshotCapture, ScreenshotRecord, ScreenshotStore};
 use crate::session::SessionManager;
 use chrono::Utc;
-use std::path::PathBuf;
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
     config: &Config,
+    session: &str,
     grid_size_override: Option<u32>,
     duration_override: Option<u32>,
     output_override: Option<String>,
     export_json: bool,
+    budget: &ImageBudget,
+    emit: Option<EmitFormat>,
+    settle: bool,
 ) -> Result<()> {
+    // With --emit, stdout carries only the emitted document
+    let log = |message: String| {
+        if emit.is_some() {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    };
+    
+    // Reject bad --output names before waiting on the overlay
+    let output_override = output_override
+        .map(|name| SessionManager::sanitize_output_name(&name))
+        .transpose()?;
+    
     // Get screen dimensions
     let (screen_width, screen_height) = ScreenshotCapture::get_primary_display_size()?;
     
@@ -28,56 +47,126 @@ pub fn execute(
         grid_size
     );
     
-    println!("Generated overview grid: {}x{} squares ({}x{} pixels)", 
-             screen_info.cols, screen_info.rows, screen_width, screen_height);
+    log(format!("Generated overview grid: {}x{} squares ({}x{} pixels)", 
+                screen_info.cols, screen_info.rows, screen_width, screen_height));
     
     // Show overlay with grid (simplified version)
-    SimpleOverlay::show_overview_grid(duration)?;
+    SimpleOverlay::show_overview_grid(&GridStyle::overview(&config.overview_grid)?, duration)?;
     
-    println!("Overlay displayed for {} seconds", duration);
+    log(format!("Overlay displayed for {} seconds", duration));
+    
+    // Menus fading in or pages still loading would be captured half-drawn
+    if settle {
+        let settled = wait_stable(None, &config.settle)?;
+        if settled.stable {
+            log(format!("Screen settled after {} ms", settled.elapsed.as_millis()));
+        } else {
+            eprintln!("⚠ Screen did not settle within {} ms; capturing anyway", settled.elapsed.as_millis());
+        }
+    }
     
     // Take screenshot
-    let screenshots_dir = SessionManager::get_screenshots_dir()?;
-    let filename = if let Some(custom_name) = output_override {
-        custom_name
+    let session_id = SessionManager::generate_session_id();
+    let mut image = ScreenshotCapture::capture_screen()?;
+    // Fingerprint the screen itself, before the grid is drawn over it
+    let fingerprint = ScreenFingerprint::new(&image)?;
+    // Templates are cropped from the capture as it was, before the grid
+    let keep_clean_copy = config.display.keep_clean_copy;
+    let clean = (keep_clean_copy && config.display.draw_grid).then(|| image.clone());
+    if config.display.draw_grid {
+        let placement = Placement::new(&image, 0, 0, screen_width);
+        GridRenderer::draw_overview_grid(&mut image, &placement, &overview_squares, &config.overview_grid)?;
+    }
+    let encoded = budget.encode(&image, &config.display.output_format)?;
+    
+    let screenshots_dir = SessionManager::get_screenshots_dir(config)?;
+    
+    let (output_path, clean_path) = if emit.is_some() {
+        (None, None)
+    } else if !config.display.save_screenshots {
+        // In-memory mode: the session and JSON export still reference the grid
+        log("Screenshot not saved (display.save_screenshots = false)".to_string());
+        (None, None)
     } else {
-        SessionManager::generate_screenshot_filename("overview", &config.display.output_format)
+        let output_path = SessionManager::write_screenshot(
+            &screenshots_dir,
+            output_override.as_deref(),
+            "overview",
+            &encoded.format,
+            &encoded.bytes,
+        )?;
+        log(format!("Screenshot saved: {:?}", output_path));
+        
+        let lossy = keep_clean_copy && matches!(encoded.format.as_str(), "jpeg" | "jpg");
+        let clean_path = match clean.as_ref().or(lossy.then_some(&image)) {
+            Some(clean) => Some(ScreenshotStore::save_clean_copy(clean, &output_path)?),
+            None => None,
+        };
+        
+        let record = ScreenshotRecord::new(&output_path, CaptureKind::Overview, Some(session_id.clone()))?
+            .with_grid(GridParameters::Overview(screen_info.clone()))
+            .with_clean_copy(clean_path.as_deref())?;
+        let pruned = ScreenshotStore::record(config, record)?;
+        if !pruned.is_empty() {
+            log(format!("Retention policy removed {} old screenshot(s)", pruned.len()));
+        }
+        
+        (Some(output_path), clean_path)
     };
     
-    let output_path = screenshots_dir.join(&filename);
-    let (_captured_width, _captured_height) = ScreenshotCapture::capture_and_save(
-        &output_path, 
-        &config.display.output_format
-    )?;
-    
-    println!("Screenshot saved: {:?}", output_path);
+    if encoded.width != image.width() || encoded.format != config.display.output_format {
+        log(format!("Image fitted to budget: {}", encoded.describe()));
+    }
     
     // Create session data
-    let session_data = SessionData {
+    let mut session_data = SessionData {
+        version: SESSION_VERSION,
         overview_grid: overview_squares.clone(),
         selected_square: None,
         zoom_area: None,
         zoom_grid: vec![],
         screen_info,
         timestamp: Utc::now(),
+        overview_image: Some(CapturedImage::new(
+            output_path,
+            &encoded.format,
+            encoded.width,
+            encoded.height,
+            0,
+            0,
+            screen_width,
+        ).with_grid_drawn(config.display.draw_grid).with_clean_path(clean_path)),
+        zoom_image: None,
+        session_id: Some(session_id),
+        fingerprint: Some(fingerprint),
+        history: vec![],
+        current_step: None,
     };
+    session_data.record_step("overview");
     
     // Save session
-    SessionManager::save_session(&session_data)?;
+    SessionManager::save_session(session, &session_data)?;
     
     if export_json {
-        let json_filename = PathBuf::from(&filename).with_extension("json");
-        let json_path = screenshots_dir.join(json_filename);
+        let output_path = session_data.overview_image.as_ref().and_then(|captured| captured.path.as_deref());
+        let json_path = SessionManager::json_export_path(&screenshots_dir, output_path, output_override.as_deref(), "overview")?;
         let json_data = serde_json::to_string_pretty(&session_data)?;
         std::fs::write(&json_path, json_data)?;
-        println!("Grid data exported: {:?}", json_path);
+        log(format!("Grid data exported: {:?}", json_path));
+        if let Some(output_path) = output_path {
+            ScreenshotStore::record_export(config, output_path, &json_path)?;
+        }
+    }
+    
+    if let (Some(EmitFormat::Json), Some(overview_image)) = (emit, &session_data.overview_image) {
+        EmitDocument::new("overview", &encoded, overview_image, &session_data)?.print()?;
     }
     
-    println!("Overview command completed successfully!");
-    println!("Use 'gui-helper zoom --square <ID>' to zoom into a specific square");
-    println!("Available squares: A1-{}{}", 
-             char::from(b'A' + (session_data.screen_info.rows - 1) as u8),
-             session_data.screen_info.cols);
+    log("Overview command completed successfully!".to_string());
+    log("Use 'gui-helper zoom --square <ID>' to zoom into a specific square".to_string());
+    log(format!("Available squares: A1-{}{}", 
+                char::from(b'A' + (session_data.screen_info.rows - 1) as u8),
+                session_data.screen_info.cols));
     
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,156 @@
+use anyhow::Result;
+use crate::automation::WindowController;
+use crate::config::Config;
+use crate::grid::GridGenerator;
+use crate::screenshot::{CaptureKind, ScreenshotCapture, ScreenshotRecord, ScreenshotStore, WindowCapture};
+use crate::session::SessionManager;
+use image::imageops::FilterType;
+use image::RgbaImage;
+use std::io::Write;
+
+// Upper bounds for `--scale`, so a typo can't ask for gigapixels
+const MAX_SCALE: f32 = 8.0;
+const MAX_SCALED_DIMENSION: u32 = 16_384;
+
+pub struct ScreenshotOptions {
+    pub region: Option<String>,
+    pub square: Option<String>,
+    pub window: Option<String>,
+    pub scale: Option<f32>,
+    pub format: Option<String>,
+    pub quality: Option<u8>,
+    pub output: Option<String>,
+    pub stdout: bool,
+}
+
+pub fn execute(config: &Config, session: &str, options: ScreenshotOptions) -> Result<()> {
+    // Keep stdout clean for the image bytes when streaming
+    let log = |message: String| {
+        if options.stdout {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    };
+    
+    let format = options.format.clone().unwrap_or_else(|| config.display.output_format.clone());
+    
+    // Only JPEG is lossy; the PNG and WebP encoders are lossless and have no quality
+    if options.quality.is_some() && !matches!(format.to_lowercase().as_str(), "jpeg" | "jpg") {
+        return Err(anyhow::anyhow!(
+            "--quality only applies to JPEG; {} output is always lossless",
+            format.to_lowercase()
+        ));
+    }
+    
+    // NaN fails every comparison, so test for the valid range instead
+    if let Some(scale) = options.scale {
+        if !(scale > 0.0 && scale <= MAX_SCALE) {
+            return Err(anyhow::anyhow!("Scale must be greater than 0 and at most {}, got {}", MAX_SCALE, scale));
+        }
+    }
+    
+    let mut image = if let Some(selector) = &options.window {
+        capture_window(selector, &log)?
+    } else if let Some(region) = &options.region {
+        let (x, y, width, height) = parse_region(region)?;
+        log(format!("Capturing region {}x{} at ({}, {})", width, height, x, y));
+        ScreenshotCapture::capture_area(x, y, width, height)?
+    } else if let Some(square_id) = &options.square {
+        let (x, y, width, height) = resolve_square(config, session, square_id)?;
+        log(format!("Capturing square '{}' ({}x{} at ({}, {}))", square_id, width, height, x, y));
+        ScreenshotCapture::capture_area(x, y, width, height)?
+    } else {
+        log("Capturing full screen".to_string());
+        ScreenshotCapture::capture_screen()?
+    };
+    
+    if let Some(scale) = options.scale {
+        let width = ((image.width() as f32 * scale).round() as u32).max(1);
+        let height = ((image.height() as f32 * scale).round() as u32).max(1);
+        if width.max(height) > MAX_SCALED_DIMENSION {
+            return Err(anyhow::anyhow!(
+                "Scaling {}x{} by {} gives {}x{}; the longer side may be at most {} pixels",
+                image.width(), image.height(), scale, width, height, MAX_SCALED_DIMENSION
+            ));
+        }
+        image = image::imageops::resize(&image, width, height, FilterType::Triangle);
+    }
+    
+    let bytes = ScreenshotCapture::encode_image(&image, &format, options.quality)?;
+    
+    if options.stdout {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&bytes)?;
+        stdout.flush()?;
+        log(format!("Wrote {} bytes of {} ({}x{}) to stdout", bytes.len(), format, image.width(), image.height()));
+        return Ok(());
+    }
+    
+    let screenshots_dir = SessionManager::get_screenshots_dir(config)?;
+    let custom_name = options.output.as_deref()
+        .map(SessionManager::sanitize_output_name)
+        .transpose()?;
+    let output_path = SessionManager::write_screenshot(&screenshots_dir, custom_name.as_deref(), "screenshot", &format, &bytes)?;
+    
+    log(format!("Screenshot saved: {:?} ({}x{})", output_path, image.width(), image.height()));
+    
+    let mut record = ScreenshotRecord::new(&output_path, CaptureKind::Screenshot, None)?;
+    record.parent_square = options.square.clone();
+    let pruned = ScreenshotStore::record(config, record)?;
+    if !pruned.is_empty() {
+        log(format!("Retention policy removed {} old screenshot(s)", pruned.len()));
+    }
+    
+    Ok(())
+}
+
+fn capture_window(selector: &str, log: &dyn Fn(String)) -> Result<RgbaImage> {
+    let (window_id, window_title) = WindowController::find_window(selector)?;
+    
+    log(format!("Capturing window [{}] {}", window_id, window_title));
+    
+    match WindowCapture::capture_direct(&window_id) {
+        Ok(image) => {
+            log("Captured window contents directly (occluding windows are not included)".to_string());
+            Ok(image)
+        }
+        Err(e) => {
+            eprintln!("⚠ Direct window capture unavailable: {}", e);
+            eprintln!("Falling back to raising the window and capturing its screen area");
+            WindowCapture::capture_raised(&window_id)
+        }
+    }
+}
+
+/// Parse "x,y,width,height" as used by `--region`.
+pub fn parse_region(region: &str) -> Result<(u32, u32, u32, u32)> {
+    let parts: Vec<u32> = region
+        .split(',')
+        .map(|part| part.trim().parse::<u32>())
+        .collect::<std::result::Result<_, _>>()
+        .map_err(|_| anyhow::anyhow!("Invalid region '{}'. Expected x,y,width,height", region))?;
+    
+    match parts[..] {
+        [x, y, width, height] if width > 0 && height > 0 => Ok((x, y, width, height)),
+        _ => Err(anyhow::anyhow!("Invalid region '{}'. Expected x,y,width,height", region)),
+    }
+}
+
+/// Screen rectangle of an overview square, from the session's grid if there is one.
+pub fn resolve_square(config: &Config, session: &str, square_id: &str) -> Result<(u32, u32, u32, u32)> {
+    // Prefer the grid the agent is looking at; otherwise rebuild it from config.
+    // The session is only read, never refreshed or expired.
+    let overview_grid = match SessionManager::read_session(session)? {
+        Some(session_data) => session_data.overview_grid,
+        None => {
+            let (screen_width, screen_height) = ScreenshotCapture::get_primary_display_size()?;
+            GridGenerator::generate_overview_grid(screen_width, screen_height, config.overview_grid.square_size).0
+        }
+    };
+    
+    let square = GridGenerator::find_square_by_id(&overview_grid, square_id)
+        .ok_or_else(|| anyhow::anyhow!("Square '{}' not found in overview grid", square_id))?;
+    
+    Ok((square.x, square.y, square.width, square.height))
+}
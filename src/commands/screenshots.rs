@@ -0,0 +1,83 @@
+use anyhow::Result;
+use crate::config::Config;
+use crate::screenshot::{RetentionPolicy, ScreenshotRecord, ScreenshotStore};
+
+pub fn list(config: &Config, json: bool) -> Result<()> {
+    let records = ScreenshotStore::list(config)?;
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&records)?);
+        return Ok(());
+    }
+    
+    if records.is_empty() {
+        println!("No screenshots found in {:?}", config.screenshots_dir()?);
+        return Ok(());
+    }
+    
+    println!("Screenshots in {:?}:", config.screenshots_dir()?);
+    for record in &records {
+        print_record(record);
+    }
+    
+    let total: u64 = records.iter().map(|record| record.size_bytes).sum();
+    println!("{} screenshot(s), {}", records.len(), format_size(total));
+    
+    Ok(())
+}
+
+pub fn prune(config: &Config, policy: RetentionPolicy, dry_run: bool) -> Result<()> {
+    // Fall back to the configured retention policy when no limit is given
+    let policy = if policy.keep.is_none() && policy.older_than.is_none() && policy.max_size.is_none() {
+        RetentionPolicy::from_config(&config.retention)?
+            .ok_or_else(|| anyhow::anyhow!(
+                "No limits given. Use --keep, --older-than or --max-size, or set [retention] in the config"
+            ))?
+    } else {
+        policy
+    };
+    
+    let removed = ScreenshotStore::prune(config, &policy, dry_run)?;
+    
+    if removed.is_empty() {
+        println!("Nothing to prune");
+        return Ok(());
+    }
+    
+    println!("{} {} screenshot(s):", if dry_run { "Would remove" } else { "Removed" }, removed.len());
+    for record in &removed {
+        print_record(record);
+    }
+    
+    let total: u64 = removed.iter().map(|record| record.size_bytes).sum();
+    println!("{} {}", if dry_run { "Would free" } else { "Freed" }, format_size(total));
+    
+    Ok(())
+}
+
+fn print_record(record: &ScreenshotRecord) {
+    let local_time = record.created_at.with_timezone(&chrono::Local);
+    println!("  {}  {:<10} {:<6} {:>10}  {}",
+             local_time.format("%Y-%m-%d %H:%M:%S"),
+             format!("{:?}", record.kind).to_lowercase(),
+             record.parent_square.as_deref().unwrap_or("-"),
+             format_size(record.size_bytes),
+             record.filename);
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -1,39 +1,237 @@
 use anyhow::Result;
-use crate::session::SessionManager;
+use crate::bundle::SessionBundle;
+use crate::config::Config;
+use crate::grid::{GridStep, SessionData};
+use crate::session::{ActionEntry, SessionManager};
+use std::path::Path;
 
-pub fn execute(clear: bool) -> Result<()> {
+pub fn execute(config: &Config, session: &str, clear: bool) -> Result<()> {
     if clear {
-        SessionManager::clear_session()?;
-        println!("Session data cleared");
+        SessionManager::clear_session(session)?;
+        println!("Session '{}' cleared", session);
         return Ok(());
     }
     
     // Show session status
-    match SessionManager::load_session()? {
+    match SessionManager::load_session(config, session)? {
         Some(session_data) => {
-            println!("Active session found:");
-            println!("  Screen: {}x{}", session_data.screen_info.width, session_data.screen_info.height);
-            println!("  Grid: {}x{} squares", session_data.screen_info.cols, session_data.screen_info.rows);
-            let local_time = session_data.timestamp.with_timezone(&chrono::Local);
-            println!("  Timestamp: {}", local_time.format("%B %d, %Y at %l:%M %p"));
-            
-            if let Some(selected_square) = &session_data.selected_square {
-                println!("  Selected square: {}", selected_square);
-            }
-            
-            if let Some(zoom_area) = &session_data.zoom_area {
-                println!("  Zoom area: {} ({}x{} with {} padding)", 
-                         zoom_area.parent_square, 
-                         zoom_area.width, 
-                         zoom_area.height,
-                         zoom_area.padding);
-                println!("  Zoom squares: {}", session_data.zoom_grid.len());
+            println!("Active session '{}' found:", session);
+            print_session(&session_data);
+        }
+        None => {
+            println!("No active session '{}' found", session);
+        }
+    }
+    
+    Ok(())
+}
+
+pub fn list(config: &Config) -> Result<()> {
+    let entries = SessionManager::list_sessions()?;
+    
+    if entries.is_empty() {
+        println!("No sessions found");
+        return Ok(());
+    }
+    
+    println!("Sessions:");
+    for entry in &entries {
+        match &entry.data {
+            Some(session_data) => {
+                let local_time = session_data.timestamp.with_timezone(&chrono::Local);
+                let expired = SessionManager::is_expired(config, session_data);
+                println!("  {:<20} {}  {}x{} grid  zoom: {}{}",
+                         entry.name,
+                         local_time.format("%Y-%m-%d %H:%M:%S"),
+                         session_data.screen_info.cols,
+                         session_data.screen_info.rows,
+                         session_data.selected_square.as_deref().unwrap_or("-"),
+                         if expired { "  (expired)" } else { "" });
             }
+            None => println!("  {:<20} (unreadable)", entry.name),
+        }
+    }
+    
+    Ok(())
+}
+
+pub fn show(name: &str) -> Result<()> {
+    // Unlike the default view, this never expires the session
+    let session_data = SessionManager::read_session(name)?
+        .ok_or_else(|| anyhow::anyhow!("Session '{}' not found. See 'gui-helper session list'.", name))?;
+    
+    println!("Session '{}':", name);
+    print_session(&session_data);
+    Ok(())
+}
+
+pub fn delete(name: &str) -> Result<()> {
+    if SessionManager::delete_session(name)? {
+        println!("Session '{}' deleted", name);
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Session '{}' not found", name))
+    }
+}
+
+pub fn back(config: &Config, session: &str) -> Result<()> {
+    let (lock, session_data) = SessionManager::load_session_for_update(config, session)?;
+    let mut session_data = session_data
+        .ok_or_else(|| anyhow::anyhow!("No active session '{}' found. Run 'gui-helper overview' first.", session))?;
+    
+    let previous = session_data.current_step
+        .and_then(|current| session_data.history.get(current))
+        .and_then(|grid_step| grid_step.previous)
+        .ok_or_else(|| anyhow::anyhow!("Session '{}' is already at its first step", session))?;
+    
+    let restored = session_data.restore_step(previous)
+        .ok_or_else(|| anyhow::anyhow!("Step {} missing from session history", previous))?;
+    println!("Restored step {}: {}", restored.step, restored.command);
+    
+    let session_data = SessionManager::update_session_timestamp(&lock, session_data)?;
+    print_session(&session_data);
+    Ok(())
+}
+
+pub fn history(config: &Config, session: &str, json: bool) -> Result<()> {
+    let session_data = SessionManager::load_session(config, session)?
+        .ok_or_else(|| anyhow::anyhow!("No active session '{}' found. Run 'gui-helper overview' first.", session))?;
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&session_data.history)?);
+        return Ok(());
+    }
+    
+    if session_data.history.is_empty() {
+        println!("Session '{}' has no recorded history", session);
+        return Ok(());
+    }
+    
+    println!("History of session '{}' (* = current):", session);
+    for grid_step in &session_data.history {
+        let local_time = grid_step.timestamp.with_timezone(&chrono::Local);
+        let marker = if session_data.current_step == Some(grid_step.step) { "*" } else { " " };
+        let previous = grid_step.previous.map(|step| format!("from {}", step)).unwrap_or_default();
+        let screenshot = grid_step.screenshot.as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "(not saved)".to_string());
+        
+        println!(" {}{:>3}  {}  {:<12} {:<8} {}",
+                 marker,
+                 grid_step.step,
+                 local_time.format("%H:%M:%S"),
+                 grid_step.command,
+                 previous,
+                 screenshot);
+    }
+    
+    Ok(())
+}
+
+pub fn export(config: &Config, session: &str, output: &Path) -> Result<()> {
+    let bundle = SessionBundle::from_session(config, session)?;
+    bundle.write(output)?;
+    
+    println!("Exported session '{}' to {:?}", session, output);
+    println!("  {} screenshot(s), {} action(s)", bundle.manifest.screenshots.len(), bundle.actions.len());
+    for file_name in &bundle.manifest.missing_screenshots {
+        eprintln!("⚠ Screenshot not found, not included: {}", file_name);
+    }
+    
+    Ok(())
+}
+
+pub fn import(bundle_path: &Path, name: Option<String>, force: bool) -> Result<()> {
+    let bundle = SessionBundle::read(bundle_path)?;
+    let name = match name {
+        Some(name) => name,
+        None => SessionManager::validate_session_name(&bundle.manifest.session_name)?,
+    };
+    
+    let screenshots = bundle.screenshots.len();
+    let import_dir = bundle.import(&name, force)?;
+    
+    println!("Imported {:?} as session '{}'", bundle_path, name);
+    println!("  {} screenshot(s) and the exported config in {:?}", screenshots, import_dir);
+    println!("Use 'gui-helper --session {} session inspect' to see the timeline", name);
+    Ok(())
+}
+
+/// Print a timeline of grid steps and actions, from a bundle file or a stored session.
+pub fn inspect(session: &str, bundle_path: Option<&Path>) -> Result<()> {
+    let (name, session_data, actions) = match bundle_path {
+        Some(bundle_path) => {
+            let bundle = SessionBundle::read(bundle_path)?;
+            let exported_at = bundle.manifest.exported_at.with_timezone(&chrono::Local);
+            println!("Bundle {:?} (exported {}, gui-helper {})",
+                     bundle_path, exported_at.format("%Y-%m-%d %H:%M:%S"), bundle.manifest.tool_version);
+            (bundle.manifest.session_name, bundle.session, bundle.actions)
         }
         None => {
-            println!("No active session found");
+            let session_data = SessionManager::read_session(session)?
+                .ok_or_else(|| anyhow::anyhow!("Session '{}' not found. See 'gui-helper session list'.", session))?;
+            (session.to_string(), session_data, SessionManager::read_actions(session)?)
+        }
+    };
+    
+    println!("Session '{}' ({}):", name, session_data.session_id.as_deref().unwrap_or("no id"));
+    print_session(&session_data);
+    
+    enum Event<'a> {
+        Step(&'a GridStep),
+        Action(&'a ActionEntry),
+    }
+    
+    let mut events: Vec<(chrono::DateTime<chrono::Utc>, Event)> = session_data.history.iter()
+        .map(|grid_step| (grid_step.timestamp, Event::Step(grid_step)))
+        .chain(actions.iter().map(|entry| (entry.timestamp, Event::Action(entry))))
+        .collect();
+    events.sort_by_key(|(timestamp, _)| *timestamp);
+    
+    if events.is_empty() {
+        println!("No recorded steps or actions");
+        return Ok(());
+    }
+    
+    println!("Timeline:");
+    for (timestamp, event) in &events {
+        let local_time = timestamp.with_timezone(&chrono::Local).format("%H:%M:%S%.3f");
+        match event {
+            Event::Step(grid_step) => {
+                let screenshot = grid_step.screenshot.as_ref()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_else(|| "(not saved)".to_string());
+                println!("  {}  step {:<3} {:<12} {}", local_time, grid_step.step, grid_step.command, screenshot);
+            }
+            Event::Action(entry) => {
+                let outcome = match &entry.error {
+                    Some(error) => format!("✗ {}", error),
+                    None => "✓".to_string(),
+                };
+                println!("  {}  $ gui-helper {}  {}", local_time, entry.command.join(" "), outcome);
+            }
         }
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+fn print_session(session_data: &SessionData) {
+    println!("  Screen: {}x{}", session_data.screen_info.width, session_data.screen_info.height);
+    println!("  Grid: {}x{} squares", session_data.screen_info.cols, session_data.screen_info.rows);
+    let local_time = session_data.timestamp.with_timezone(&chrono::Local);
+    println!("  Timestamp: {}", local_time.format("%B %d, %Y at %l:%M %p"));
+    
+    if let Some(selected_square) = &session_data.selected_square {
+        println!("  Selected square: {}", selected_square);
+    }
+    
+    if let Some(zoom_area) = &session_data.zoom_area {
+        println!("  Zoom area: {} ({}x{} with {} padding)",
+                 zoom_area.parent_square,
+                 zoom_area.width,
+                 zoom_area.height,
+                 zoom_area.padding);
+        println!("  Zoom squares: {}", session_data.zoom_grid.len());
+    }
+}
@@ -0,0 +1,308 @@
+use anyhow::Result;
+use crate::automation::WindowController;
+use crate::commands::screenshot::parse_region;
+use crate::config::Config;
+use crate::grid::{CapturedImage, GridGenerator, SessionData};
+use crate::screenshot::{ScreenFingerprint, ScreenshotCapture, TemplateInfo, TemplateLibrary};
+use crate::session::SessionManager;
+use chrono::Utc;
+use image::RgbaImage;
+use std::path::Path;
+
+/// Crop a zoom cell ("B5.23"), an overview square ("B5") or a screen region
+/// and store it in the template library as `name`.
+#[allow(clippy::too_many_arguments)]
+pub fn save(
+    config: &Config,
+    session: &str,
+    name: &str,
+    square: Option<&str>,
+    region: Option<&str>,
+    pad: u32,
+    from_screen: bool,
+    force: bool,
+) -> Result<()> {
+    TemplateLibrary::validate_name(name)?;
+    let session_data = SessionManager::load_session(config, session)?;
+    
+    let (source, (x, y, width, height)) = match (square, region) {
+        (Some(address), _) => {
+            let session_data = session_data.as_ref()
+                .ok_or_else(|| anyhow::anyhow!("No overview session found. Run 'gui-helper overview' first."))?;
+            (address.to_uppercase(), resolve_address(config, session_data, address)?)
+        }
+        (None, Some(region)) => (format!("region {}", region), parse_region(region)?),
+        (None, None) => return Err(anyhow::anyhow!("Give --square or --region to choose what to save")),
+    };
+    
+    // Pad, staying on screen
+    let (screen_width, screen_height) = match &session_data {
+        Some(session_data) => (session_data.screen_info.width, session_data.screen_info.height),
+        None => ScreenshotCapture::get_primary_display_size()?,
+    };
+    let left = x.saturating_sub(pad);
+    let top = y.saturating_sub(pad);
+    let right = (x + width + pad).min(screen_width);
+    let bottom = (y + height + pad).min(screen_height);
+    if right <= left || bottom <= top {
+        return Err(anyhow::anyhow!("{} is outside the {}x{} screen", source, screen_width, screen_height));
+    }
+    let rect = (left, top, right - left, bottom - top);
+    
+    let (image, source_image, image_scale) = match clean_capture(session_data.as_ref(), rect, from_screen) {
+        Some((captured, path)) => {
+            let (image, scale) = crop(captured, path, rect)?;
+            let filename = path.file_name().map(|filename| filename.to_string_lossy().to_string());
+            (image, filename, scale)
+        }
+        None => {
+            if !from_screen {
+                println!("No lossless screenshot without grid lines covers {}; capturing it from the live screen", source);
+                if !config.display.keep_clean_copy {
+                    println!("Set display.keep_clean_copy = true to crop from captures with the grid drawn in");
+                }
+            }
+            let image = ScreenshotCapture::capture_area(rect.0, rect.1, rect.2, rect.3)?;
+            let scale = image.width() as f64 / rect.2 as f64;
+            (image, None, scale)
+        }
+    };
+    
+    // Best effort: both need a desktop session, and templates are useful without them
+    let (center_x, center_y) = ((rect.0 + rect.2 / 2) as i32, (rect.1 + rect.3 / 2) as i32);
+    let window = WindowController::window_at(center_x, center_y).ok().flatten();
+    let display_scale = ScreenFingerprint::current_displays().ok().and_then(|displays| {
+        displays.into_iter()
+            .find(|display| {
+                center_x >= display.x && center_y >= display.y
+                    && center_x < display.x + display.width as i32 && center_y < display.y + display.height as i32
+            })
+            .map(|display| display.scale_factor)
+    });
+    
+    let info = TemplateInfo {
+        name: name.to_string(),
+        width: image.width(),
+        height: image.height(),
+        source,
+        screen_x: rect.0,
+        screen_y: rect.1,
+        screen_width: rect.2,
+        screen_height: rect.3,
+        source_image,
+        window,
+        image_scale,
+        display_scale,
+        session_id: session_data.as_ref().and_then(|session_data| session_data.session_id.clone()),
+        created_at: Utc::now(),
+    };
+    let path = TemplateLibrary::save(&image, &info, force)?;
+    
+    println!("✓ Saved template '{}' ({}x{} px): {:?}", info.name, info.width, info.height, path);
+    println!("   Source: {}, {}x{} at ({}, {}) from {}",
+             info.source, info.screen_width, info.screen_height, info.screen_x, info.screen_y,
+             info.source_image.as_deref().unwrap_or("the screen"));
+    if let Some(window) = &info.window {
+        println!("   Window: {}", window);
+    }
+    println!("Use 'gui-helper find --template {}' to look for it", info.name);
+    Ok(())
+}
+
+pub fn list(json: bool) -> Result<()> {
+    let templates = TemplateLibrary::list()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&templates)?);
+        return Ok(());
+    }
+    
+    if templates.is_empty() {
+        println!("No templates saved. Use 'gui-helper template save <NAME> --square B5.23' to add one.");
+        return Ok(());
+    }
+    
+    println!("Templates in {:?}:", TemplateLibrary::dir()?);
+    for info in &templates {
+        println!("  {:<20} {:>4}x{:<4} {:<12} {}  {}",
+                 info.name,
+                 info.width,
+                 info.height,
+                 info.source,
+                 info.created_at.format("%Y-%m-%d %H:%M"),
+                 info.window.as_deref().unwrap_or("-"));
+    }
+    println!("{} template(s)", templates.len());
+    Ok(())
+}
+
+pub fn delete(name: &str) -> Result<()> {
+    if !TemplateLibrary::delete(name)? {
+        return Err(anyhow::anyhow!("Template '{}' not found. See 'gui-helper template list'", name));
+    }
+    println!("✓ Deleted template '{}'", name);
+    Ok(())
+}
+
+// Screen rectangle of "B5" or "B5.23" (also "B5/23"). The zoom grid is the
+// session's when it belongs to that square, otherwise the one `zoom` would build.
+fn resolve_address(config: &Config, session_data: &SessionData, address: &str) -> Result<(u32, u32, u32, u32)> {
+    let address = address.to_uppercase();
+    let (square_id, cell) = match address.split_once(['.', '/']) {
+        Some((square_id, cell)) => {
+            let cell: u32 = cell.parse()
+                .map_err(|_| anyhow::anyhow!("Invalid zoom square '{}' in '{}'. Expected e.g. B5.23", cell, address))?;
+            (square_id, Some(cell))
+        }
+        None => (address.as_str(), None),
+    };
+    
+    let square = GridGenerator::find_square_by_id(&session_data.overview_grid, square_id)
+        .ok_or_else(|| anyhow::anyhow!("Square '{}' not found in overview grid", square_id))?;
+    let cell = match cell {
+        Some(cell) => cell,
+        None => return Ok((square.x, square.y, square.width, square.height)),
+    };
+    
+    let zoom_grid = match &session_data.zoom_area {
+        Some(zoom_area) if zoom_area.parent_square == square.id => session_data.zoom_grid.clone(),
+        _ => GridGenerator::generate_zoom_grid(square, config.zoom_grid.padding, config.zoom_grid.subdivision, &session_data.screen_info).1,
+    };
+    let zoom_square = GridGenerator::find_zoom_square_by_id(&zoom_grid, cell)
+        .ok_or_else(|| anyhow::anyhow!("Zoom square '{}' not found. Available: 1-{}", cell, zoom_grid.len()))?;
+    Ok((zoom_square.abs_x, zoom_square.abs_y, zoom_square.width, zoom_square.height))
+}
+
+// The session's zoom or overview capture covering `rect`, with the file to crop
+// from: its clean copy, or the saved image itself if that has no grid drawn
+// into it and isn't JPEG. The zoom image comes first for its detail.
+fn clean_capture(session_data: Option<&SessionData>, rect: (u32, u32, u32, u32), from_screen: bool) -> Option<(&CapturedImage, &Path)> {
+    if from_screen {
+        return None;
+    }
+    
+    let session_data = session_data?;
+    let (x, y, width, height) = rect;
+    [&session_data.zoom_image, &session_data.overview_image]
+        .into_iter()
+        .flatten()
+        .filter(|captured| {
+            x >= captured.origin_x
+                && y >= captured.origin_y
+                && (x + width) as f64 <= captured.origin_x as f64 + captured.width as f64 / captured.scale
+                && (y + height) as f64 <= captured.origin_y as f64 + captured.height as f64 / captured.scale
+        })
+        .find_map(|captured| {
+            let lossless = !captured.grid_drawn && !matches!(captured.format.as_str(), "jpeg" | "jpg");
+            let path = match (&captured.clean_path, &captured.path) {
+                (Some(clean_path), _) => clean_path,
+                (None, Some(path)) if lossless => path,
+                _ => return None,
+            };
+            path.exists().then_some((captured, path.as_path()))
+        })
+}
+
+// Crop `rect` from `path`, an image of `captured`'s screen area. A clean copy
+// can be larger than the saved image, so the scale comes from the file itself.
+// Also returns that scale (image pixels per screen pixel).
+fn crop(captured: &CapturedImage, path: &Path, rect: (u32, u32, u32, u32)) -> Result<(RgbaImage, f64)> {
+    let image = image::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read screenshot {:?}: {}", path, e))?
+        .to_rgba8();
+    let scale = image.width() as f64 / (captured.width as f64 / captured.scale);
+    
+    let (x, y, width, height) = rect;
+    let left = (((x - captured.origin_x) as f64 * scale).floor() as u32).min(image.width() - 1);
+    let top = (((y - captured.origin_y) as f64 * scale).floor() as u32).min(image.height() - 1);
+    let crop_width = ((width as f64 * scale).round() as u32).clamp(1, image.width() - left);
+    let crop_height = ((height as f64 * scale).round() as u32).clamp(1, image.height() - top);
+    Ok((image::imageops::crop_imm(&image, left, top, crop_width, crop_height).to_image(), scale))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{GridGenerator, SESSION_VERSION};
+    use image::Rgba;
+    use std::path::PathBuf;
+    
+    // A session whose overview covers an 800x600 screen, saved at half size
+    fn session_with(overview_image: CapturedImage) -> SessionData {
+        let (overview_grid, screen_info) = GridGenerator::generate_overview_grid(800, 600, 100);
+        SessionData {
+            version: SESSION_VERSION,
+            overview_grid,
+            selected_square: None,
+            zoom_area: None,
+            zoom_grid: vec![],
+            screen_info,
+            timestamp: Utc::now(),
+            overview_image: Some(overview_image),
+            zoom_image: None,
+            session_id: None,
+            fingerprint: None,
+            history: vec![],
+            current_step: None,
+        }
+    }
+    
+    fn temp_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("gui-helper-{}-{}", std::process::id(), name));
+        std::fs::write(&path, b"").unwrap();
+        path
+    }
+    
+    #[test]
+    fn crops_from_the_clean_copy_of_a_gridded_capture() {
+        let saved = temp_file("gridded.png");
+        let clean = temp_file("gridded.clean.png");
+        let captured = CapturedImage::new(Some(saved.clone()), "png", 400, 300, 0, 0, 800)
+            .with_grid_drawn(true)
+            .with_clean_path(Some(clean.clone()));
+        let session_data = session_with(captured);
+        
+        let (_, path) = clean_capture(Some(&session_data), (100, 100, 50, 50), false).unwrap();
+        assert_eq!(path, clean.as_path());
+        assert!(clean_capture(Some(&session_data), (100, 100, 50, 50), true).is_none());
+        assert!(clean_capture(Some(&session_data), (780, 100, 50, 50), false).is_none());
+        
+        std::fs::remove_file(saved).unwrap();
+        std::fs::remove_file(clean).unwrap();
+    }
+    
+    #[test]
+    fn never_crops_from_gridded_or_jpeg_captures() {
+        let saved = temp_file("plain.png");
+        let session_data = session_with(CapturedImage::new(Some(saved.clone()), "png", 400, 300, 0, 0, 800));
+        assert_eq!(clean_capture(Some(&session_data), (0, 0, 10, 10), false).unwrap().1, saved.as_path());
+        
+        let gridded = session_with(CapturedImage::new(Some(saved.clone()), "png", 400, 300, 0, 0, 800).with_grid_drawn(true));
+        assert!(clean_capture(Some(&gridded), (0, 0, 10, 10), false).is_none());
+        
+        let jpeg = session_with(CapturedImage::new(Some(saved.clone()), "jpeg", 400, 300, 0, 0, 800));
+        assert!(clean_capture(Some(&jpeg), (0, 0, 10, 10), false).is_none());
+        
+        std::fs::remove_file(saved).unwrap();
+    }
+    
+    #[test]
+    fn crop_uses_the_scale_of_the_file_it_reads() {
+        // The session image is half size, the clean copy full size
+        let clean = std::env::temp_dir().join(format!("gui-helper-{}-scale.clean.png", std::process::id()));
+        let mut image = RgbaImage::from_pixel(800, 600, Rgba([0, 0, 0, 255]));
+        for y in 200..250 {
+            for x in 100..160 {
+                image.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            }
+        }
+        image.save(&clean).unwrap();
+        let captured = CapturedImage::new(None, "png", 400, 300, 0, 0, 800);
+        
+        let (cropped, scale) = crop(&captured, &clean, (100, 200, 60, 50)).unwrap();
+        std::fs::remove_file(clean).unwrap();
+        
+        assert_eq!(scale, 1.0);
+        assert_eq!(cropped.dimensions(), (60, 50));
+        assert!(cropped.pixels().all(|pixel| pixel.0 == [255, 255, 255, 255]));
+    }
+}
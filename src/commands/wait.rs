@@ -0,0 +1,194 @@
+use anyhow::Result;
+use crate::commands::screenshot::{parse_region, resolve_square};
+use crate::config::Config;
+use crate::overlay::Color;
+use crate::screenshot::{wait_stable, ImageDiff, ScreenshotCapture};
+use image::RgbaImage;
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Exit code of a wait command whose condition didn't occur in time, so scripts
+/// can tell a timeout from a failure (1) or a usage error (2, from clap).
+pub const TIMEOUT_EXIT_CODE: i32 = 3;
+
+/// Error for a wait that timed out; `main` maps it to `TIMEOUT_EXIT_CODE`.
+#[derive(Debug)]
+pub struct WaitTimeout(pub String);
+
+impl fmt::Display for WaitTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for WaitTimeout {}
+
+/// Block until the screen (or `region`) stops changing, following `[settle]`.
+/// Fails with `WaitTimeout` when it has not settled by the timeout.
+pub fn stable(config: &Config, region: Option<&str>) -> Result<()> {
+    let region = region.map(parse_region).transpose()?;
+    let settle = &config.settle;
+    
+    match region {
+        Some((x, y, width, height)) => println!("Waiting for region {}x{} at ({}, {}) to settle...", width, height, x, y),
+        None => println!("Waiting for the screen to settle..."),
+    }
+    println!("   {} quiet frame(s), {} ms apart, up to {} ms", settle.frames, settle.interval, settle.timeout);
+    
+    let settled = wait_stable(region, settle)?;
+    if !settled.stable {
+        return Err(WaitTimeout(format!(
+            "Screen did not settle within {} ms ({:.2}% changed in the last frame)",
+            settled.elapsed.as_millis(), settled.last_score * 100.0
+        )).into());
+    }
+    
+    println!("✓ Screen stable after {} ms ({} captures)", settled.elapsed.as_millis(), settled.captures);
+    Ok(())
+}
+
+/// Block until an overview square, a region or the whole screen differs from
+/// how it looked when the command started by `automation.change_threshold`.
+pub fn change(config: &Config, session: &str, square: Option<&str>, region: Option<&str>) -> Result<()> {
+    let area = match (square, region) {
+        (Some(square_id), _) => Some(resolve_square(config, session, square_id)?),
+        (None, Some(region)) => Some(parse_region(region)?),
+        (None, None) => None,
+    };
+    let capture = || -> Result<RgbaImage> {
+        match area {
+            Some((x, y, width, height)) => ScreenshotCapture::capture_area(x, y, width, height),
+            None => ScreenshotCapture::capture_screen(),
+        }
+    };
+    
+    let target = match (square, area) {
+        (Some(square_id), _) => format!("square {}", square_id.to_uppercase()),
+        (None, Some((x, y, width, height))) => format!("region {}x{} at ({}, {})", width, height, x, y),
+        (None, None) => "the screen".to_string(),
+    };
+    println!("Waiting for {} to change (every {} ms, up to {} ms)...", target, config.wait.interval, config.wait.timeout);
+    
+    let baseline = capture()?;
+    let mut last_score = 0.0;
+    let elapsed = poll(config, || {
+        let diff = ImageDiff::compute(&baseline, &capture()?)?;
+        last_score = diff.score;
+        Ok(diff.changed(config.automation.change_threshold))
+    })?;
+    
+    match elapsed {
+        Some(elapsed) => {
+            println!("✓ Change in {} after {} ms ({:.2}% of pixels)", target, elapsed.as_millis(), last_score * 100.0);
+            Ok(())
+        }
+        None => Err(WaitTimeout(format!(
+            "No change in {} within {} ms ({:.2}% changed, threshold {:.2}%)",
+            target, config.wait.timeout, last_score * 100.0, config.automation.change_threshold * 100.0
+        )).into()),
+    }
+}
+
+/// Block until the screen pixel at `at` ("x,y") is within `tolerance` of
+/// `color` on every channel.
+pub fn pixel(config: &Config, at: &str, color: &str, tolerance: u8) -> Result<()> {
+    let (x, y) = parse_point(at)?;
+    let expected = Color::parse(color)?;
+    println!("Waiting for pixel ({}, {}) to be {} ±{} (every {} ms, up to {} ms)...",
+             x, y, Color { a: 255, ..expected }, tolerance, config.wait.interval, config.wait.timeout);
+    
+    let mut current = Color::TRANSPARENT;
+    let elapsed = poll(config, || {
+        let [r, g, b, _] = ScreenshotCapture::capture_area(x, y, 1, 1)?.get_pixel(0, 0).0;
+        current = Color::rgb(r, g, b);
+        Ok(current.r.abs_diff(expected.r) <= tolerance
+            && current.g.abs_diff(expected.g) <= tolerance
+            && current.b.abs_diff(expected.b) <= tolerance)
+    })?;
+    
+    match elapsed {
+        Some(elapsed) => {
+            println!("✓ Pixel ({}, {}) is {} after {} ms", x, y, current, elapsed.as_millis());
+            Ok(())
+        }
+        None => Err(WaitTimeout(format!(
+            "Pixel ({}, {}) is still {} after {} ms",
+            x, y, current, config.wait.timeout
+        )).into()),
+    }
+}
+
+// Check `condition` every `wait.interval` until it holds (returning the time
+// taken) or `wait.timeout` passes (returning `None`). Checks at least once.
+fn poll(config: &Config, mut condition: impl FnMut() -> Result<bool>) -> Result<Option<Duration>> {
+    let start = Instant::now();
+    let timeout = Duration::from_millis(config.wait.timeout);
+    loop {
+        if condition()? {
+            return Ok(Some(start.elapsed()));
+        }
+        if start.elapsed() >= timeout {
+            return Ok(None);
+        }
+        thread::sleep(Duration::from_millis(config.wait.interval));
+    }
+}
+
+fn parse_point(point: &str) -> Result<(u32, u32)> {
+    let parts: Vec<u32> = point
+        .split(',')
+        .map(|part| part.trim().parse::<u32>())
+        .collect::<std::result::Result<_, _>>()
+        .map_err(|_| anyhow::anyhow!("Invalid position '{}'. Expected x,y", point))?;
+    
+    match parts[..] {
+        [x, y] => Ok((x, y)),
+        _ => Err(anyhow::anyhow!("Invalid position '{}'. Expected x,y", point)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parses_points() {
+        assert_eq!(parse_point("10,20").unwrap(), (10, 20));
+        assert_eq!(parse_point(" 10 , 20 ").unwrap(), (10, 20));
+        for point in ["", "10", "10,20,30", "-1,5", "a,b"] {
+            assert!(parse_point(point).is_err(), "{:?}", point);
+        }
+    }
+    
+    #[test]
+    fn poll_checks_at_least_once_before_timing_out() {
+        let mut config = Config::default();
+        config.wait.timeout = 0;
+        
+        let mut checks = 0;
+        let elapsed = poll(&config, || {
+            checks += 1;
+            Ok(false)
+        }).unwrap();
+        assert_eq!((elapsed, checks), (None, 1));
+        
+        assert!(poll(&config, || Ok(true)).unwrap().is_some());
+        assert!(poll(&config, || Err(anyhow::anyhow!("capture failed"))).is_err());
+    }
+    
+    #[test]
+    fn poll_retries_until_the_condition_holds() {
+        let mut config = Config::default();
+        config.wait.timeout = 10_000;
+        config.wait.interval = 1;
+        
+        let mut checks = 0;
+        let elapsed = poll(&config, || {
+            checks += 1;
+            Ok(checks == 3)
+        }).unwrap();
+        assert!(elapsed.is_some());
+        assert_eq!(checks, 3);
+    }
+}
@@ -1,22 +1,42 @@
 use anyhow::Result;
 use crate::config::Config;
-use crate::grid::GridGenerator;
-use crate::overlay::SimpleOverlay;
-use crate::screenshot::ScreenshotCapture;
+use crate::emit::{EmitDocument, EmitFormat};
+use crate::grid::{CapturedImage, GridGenerator, OverviewSquare, SessionData, ZoomArea};
+use crate::overlay::{Color, GridRenderer, GridStyle, Placement, SimpleOverlay};
+use crate::screenshot::{perceptual_hash, CaptureKind, GridParameters, ImageBudget, ScreenshotCapture, ScreenshotRecord, ScreenshotStore};
 use crate::session::SessionManager;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
     config: &Config,
+    session: &str,
     square_id: &str,
     padding_override: Option<u32>,
     subdivision_override: Option<u32>,
     duration_override: Option<u32>,
     output_override: Option<String>,
     export_json: bool,
+    budget: &ImageBudget,
+    emit: Option<EmitFormat>,
 ) -> Result<()> {
-    // Load session data
-    let mut session_data = SessionManager::load_session()?
+    // With --emit, stdout carries only the emitted document
+    let log = |message: String| {
+        if emit.is_some() {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    };
+    
+    // Reject bad --output names before waiting on the overlay
+    let output_override = output_override
+        .map(|name| SessionManager::sanitize_output_name(&name))
+        .transpose()?;
+    
+    // Load session data, locked until the zoom is stored
+    let (lock, session_data) = SessionManager::load_session_for_update(config, session)?;
+    let mut session_data = session_data
         .ok_or_else(|| anyhow::anyhow!("No overview session found. Run 'gui-helper overview' first."))?;
     
     // Find the selected square
@@ -24,8 +44,8 @@ pub fn execute(
         .ok_or_else(|| anyhow::anyhow!("Square '{}' not found in overview grid", square_id))?
         .clone();
     
-    println!("Zooming into square '{}' at ({}, {})", 
-             parent_square.id, parent_square.x, parent_square.y);
+    log(format!("Zooming into square '{}' at ({}, {})", 
+                parent_square.id, parent_square.x, parent_square.y));
     
     // Use override or config values
     let padding = padding_override.unwrap_or(config.zoom_grid.padding);
@@ -33,58 +53,159 @@ pub fn execute(
     let duration = duration_override.unwrap_or(config.display.duration);
     
     // Generate zoom grid
-    let (zoom_area, zoom_squares) = GridGenerator::generate_zoom_grid(&parent_square, padding, subdivision);
+    let (zoom_area, zoom_squares) = GridGenerator::generate_zoom_grid(
+        &parent_square,
+        padding,
+        subdivision,
+        &session_data.screen_info,
+    );
     
-    println!("Generated zoom grid: {}x{} squares in area {}x{}", 
-             subdivision, subdivision, zoom_area.width, zoom_area.height);
+    log(format!("Generated zoom grid: {}x{} squares in area {}x{}", 
+                subdivision, subdivision, zoom_area.width, zoom_area.height));
     
     // Show overlay with zoom grid (simplified version)
     let zoom_info = format!("Zooming area {}x{} at ({}, {})", 
                            zoom_area.width, zoom_area.height, zoom_area.x, zoom_area.y);
-    SimpleOverlay::show_zoom_grid(&zoom_info, duration)?;
+    SimpleOverlay::show_zoom_grid(&zoom_info, &GridStyle::zoom(&config.zoom_grid)?, duration)?;
     
-    println!("Zoom overlay displayed for {} seconds", duration);
+    log(format!("Zoom overlay displayed for {} seconds", duration));
     
     // Take screenshot of zoom area
-    let screenshots_dir = SessionManager::get_screenshots_dir()?;
-    let filename = if let Some(custom_name) = output_override {
-        custom_name
-    } else {
-        SessionManager::generate_screenshot_filename(
-            &format!("zoom_{}", square_id), 
-            &config.display.output_format
-        )
-    };
-    
-    let output_path = screenshots_dir.join(&filename);
-    ScreenshotCapture::capture_area_and_save(
+    let mut image = ScreenshotCapture::capture_area(
         zoom_area.x,
         zoom_area.y,
         zoom_area.width,
         zoom_area.height,
-        &output_path,
-        &config.display.output_format,
     )?;
+    // Hash the screen itself, before the grid is drawn over it
+    let zoom_hash = perceptual_hash(&image);
+    // Templates are cropped from the capture as it was, before the grid
+    let keep_clean_copy = config.display.keep_clean_copy;
+    let clean = (keep_clean_copy && config.display.draw_grid).then(|| image.clone());
+    if config.display.draw_grid {
+        let placement = Placement::new(&image, zoom_area.x, zoom_area.y, zoom_area.width);
+        GridRenderer::draw_zoom_grid(&mut image, &placement, &zoom_area, &zoom_squares, &config.zoom_grid)?;
+    }
+    let encoded = budget.encode(&image, &config.display.output_format)?;
     
-    println!("Zoom screenshot saved: {:?}", output_path);
+    let screenshots_dir = SessionManager::get_screenshots_dir(config)?;
+    let prefix = format!("zoom_{}", parent_square.id);
+    
+    let (output_path, clean_path) = if emit.is_some() {
+        (None, None)
+    } else if !config.display.save_screenshots {
+        // In-memory mode: the session and JSON export still reference the grid
+        log("Screenshot not saved (display.save_screenshots = false)".to_string());
+        (None, None)
+    } else {
+        let output_path = SessionManager::write_screenshot(
+            &screenshots_dir,
+            output_override.as_deref(),
+            &prefix,
+            &encoded.format,
+            &encoded.bytes,
+        )?;
+        log(format!("Zoom screenshot saved: {:?}", output_path));
+        
+        let lossy = keep_clean_copy && matches!(encoded.format.as_str(), "jpeg" | "jpg");
+        let clean_path = match clean.as_ref().or(lossy.then_some(&image)) {
+            Some(clean) => Some(ScreenshotStore::save_clean_copy(clean, &output_path)?),
+            None => None,
+        };
+        
+        let record = ScreenshotRecord::new(&output_path, CaptureKind::Zoom, session_data.session_id.clone())?
+            .with_grid(GridParameters::Zoom(zoom_area.clone()))
+            .with_clean_copy(clean_path.as_deref())?;
+        let pruned = ScreenshotStore::record(config, record)?;
+        if !pruned.is_empty() {
+            log(format!("Retention policy removed {} old screenshot(s)", pruned.len()));
+        }
+        
+        match save_selection_image(config, &session_data, &parent_square, &zoom_area, &screenshots_dir)? {
+            Some(selection_path) => log(format!("Selection image saved: {:?}", selection_path)),
+            None => log("No overview image on disk; selection image skipped".to_string()),
+        }
+        
+        (Some(output_path), clean_path)
+    };
+    
+    if encoded.width != image.width() || encoded.format != config.display.output_format {
+        log(format!("Image fitted to budget: {}", encoded.describe()));
+    }
     
     // Update session data
+    session_data.zoom_image = Some(CapturedImage::new(
+        output_path,
+        &encoded.format,
+        encoded.width,
+        encoded.height,
+        zoom_area.x,
+        zoom_area.y,
+        zoom_area.width,
+    ).with_grid_drawn(config.display.draw_grid).with_clean_path(clean_path));
+    if let Some(fingerprint) = session_data.fingerprint.as_mut() {
+        fingerprint.zoom_hash = Some(zoom_hash);
+    }
     session_data.selected_square = Some(square_id.to_string());
     session_data.zoom_area = Some(zoom_area);
     session_data.zoom_grid = zoom_squares.clone();
-    session_data = SessionManager::update_session_timestamp(session_data)?;
+    session_data.record_step(&format!("zoom {}", parent_square.id));
+    session_data = SessionManager::update_session_timestamp(&lock, session_data)?;
     
     if export_json {
-        let json_filename = PathBuf::from(&filename).with_extension("json");
-        let json_path = screenshots_dir.join(json_filename);
+        let output_path = session_data.zoom_image.as_ref().and_then(|captured| captured.path.as_deref());
+        let json_path = SessionManager::json_export_path(&screenshots_dir, output_path, output_override.as_deref(), &prefix)?;
         let json_data = serde_json::to_string_pretty(&session_data)?;
         std::fs::write(&json_path, json_data)?;
-        println!("Zoom grid data exported: {:?}", json_path);
+        log(format!("Zoom grid data exported: {:?}", json_path));
+        if let Some(output_path) = output_path {
+            ScreenshotStore::record_export(config, output_path, &json_path)?;
+        }
     }
     
-    println!("Zoom command completed successfully!");
-    println!("Use 'gui-helper click --zoom <NUMBER>' to click on a zoom square");
-    println!("Available zoom squares: 1-{}", zoom_squares.len());
+    if let (Some(EmitFormat::Json), Some(zoom_image)) = (emit, &session_data.zoom_image) {
+        EmitDocument::new("zoom", &encoded, zoom_image, &session_data)?.print()?;
+    }
+    
+    log("Zoom command completed successfully!".to_string());
+    log("Use 'gui-helper click --zoom <NUMBER>' to click on a zoom square".to_string());
+    log(format!("Available zoom squares: 1-{}", zoom_squares.len()));
     
     Ok(())
-}
\ No newline at end of file
+}
+
+// Copy of the session's overview image with the zoomed square shaded, so the
+// choice can be checked afterwards. `None` when the overview wasn't saved.
+fn save_selection_image(
+    config: &Config,
+    session_data: &SessionData,
+    square: &OverviewSquare,
+    zoom_area: &ZoomArea,
+    screenshots_dir: &Path,
+) -> Result<Option<PathBuf>> {
+    let overview = match &session_data.overview_image {
+        Some(overview) => overview,
+        None => return Ok(None),
+    };
+    let overview_path = match &overview.path {
+        Some(path) if path.exists() => path,
+        _ => return Ok(None),
+    };
+    
+    let mut image = image::open(overview_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read overview image {:?}: {}", overview_path, e))?
+        .to_rgba8();
+    let placement = Placement::new(&image, overview.origin_x, overview.origin_y, session_data.screen_info.width);
+    let highlight = Color::parse(&config.overview_grid.highlight)?;
+    GridRenderer::shade_rect(&mut image, &placement, square.x, square.y, square.width, square.height, highlight, 3);
+    
+    let format = config.display.output_format.to_lowercase();
+    let bytes = ScreenshotCapture::encode_image(&image, &format, None)?;
+    let output_path = SessionManager::write_screenshot(screenshots_dir, None, &format!("selection_{}", square.id), &format, &bytes)?;
+    
+    let record = ScreenshotRecord::new(&output_path, CaptureKind::Selection, session_data.session_id.clone())?
+        .with_grid(GridParameters::Zoom(zoom_area.clone()));
+    ScreenshotStore::record(config, record)?;
+    
+    Ok(Some(output_path))
+}
@@ -0,0 +1,510 @@
+use anyhow::Result;
+use crate::config::{Config, ConfigIssue, RetentionConfig};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the per-project config file, searched from the current directory upwards.
+pub const PROJECT_CONFIG_FILE: &str = ".gui-helper.toml";
+const ENV_PREFIX: &str = "GUI_HELPER_";
+
+/// Where an effective config value came from, lowest priority first.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigOrigin {
+    Default,
+    User(PathBuf),
+    Project(PathBuf),
+    Env(String),
+    Cli,
+    Flag(String), // A command option such as `--subdivision`
+}
+
+/// The effective configuration, merged from every layer:
+/// built-in defaults, user file, project file, `GUI_HELPER_*` variables, `--set`.
+pub struct LayeredConfig {
+    pub config: Config,
+    pub origins: BTreeMap<String, ConfigOrigin>, // Dotted key -> layer that set it
+    pub user_path: PathBuf,
+    pub project_path: Option<PathBuf>,
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigOrigin::Default => write!(f, "default"),
+            ConfigOrigin::User(path) => write!(f, "user ({})", path.display()),
+            ConfigOrigin::Project(path) => write!(f, "project ({})", path.display()),
+            ConfigOrigin::Env(variable) => write!(f, "env ({})", variable),
+            ConfigOrigin::Cli => write!(f, "--set"),
+            ConfigOrigin::Flag(flag) => write!(f, "{}", flag),
+        }
+    }
+}
+
+impl LayeredConfig {
+    /// Build the effective config. `cli_overrides` are `KEY=VALUE` pairs from `--set`.
+    pub fn load(cli_overrides: &[String]) -> Result<Self> {
+        let schema = schema()?;
+        let mut merged = toml::Value::try_from(Config::default())?;
+        let mut origins = BTreeMap::new();
+        for (key, _) in leaves(&merged) {
+            origins.insert(key, ConfigOrigin::Default);
+        }
+        
+        let user_path = Config::config_path()?;
+        let user_value = Config::load_user_file()?;
+        merge(&mut merged, &user_value, &schema, &ConfigOrigin::User(user_path.clone()), &mut origins)?;
+        
+        let project_path = find_project_file()?;
+        if let Some(project_path) = &project_path {
+            let contents = fs::read_to_string(project_path)?;
+            let project_value: toml::Value = toml::from_str(&contents)
+                .map_err(|e| anyhow::anyhow!("Invalid {:?}: {}", project_path, e))?;
+            merge(&mut merged, &project_value, &schema, &ConfigOrigin::Project(project_path.clone()), &mut origins)?;
+        }
+        
+        for (key, leaf) in leaves(&schema) {
+            let variable = env_variable(&key);
+            if let Ok(raw) = std::env::var(&variable) {
+                let value = parse_value(&key, &raw, leaf)?;
+                set_key(&mut merged, &key, value)?;
+                origins.insert(key, ConfigOrigin::Env(variable));
+            }
+        }
+        
+        for assignment in cli_overrides {
+            let (key, raw) = assignment.split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Invalid --set '{}'. Expected KEY=VALUE", assignment))?;
+            let key = key.trim();
+            let value = parse_value(key, raw.trim(), schema_leaf(&schema, key)?)?;
+            set_key(&mut merged, key, value)?;
+            origins.insert(key.to_string(), ConfigOrigin::Cli);
+        }
+        
+        let config: Config = merged.try_into()
+            .map_err(|e| anyhow::anyhow!("Invalid configuration: {}", e))?;
+        
+        Ok(Self {
+            config,
+            origins,
+            user_path,
+            project_path,
+        })
+    }
+    
+    /// Built-in defaults only, for when a layer is broken and must be fixed first.
+    pub fn defaults() -> Result<Self> {
+        let config = Config::default();
+        let origins = leaves(&toml::Value::try_from(&config)?)
+            .into_iter()
+            .map(|(key, _)| (key, ConfigOrigin::Default))
+            .collect();
+        
+        Ok(Self {
+            config,
+            origins,
+            user_path: Config::config_path()?,
+            project_path: find_project_file()?,
+        })
+    }
+    
+    /// Apply a command option that overrides `key`, so it is validated like any other layer.
+    pub fn override_flag(&mut self, key: &str, flag: &str, value: Option<toml::Value>) -> Result<()> {
+        if let Some(value) = value {
+            let mut effective = toml::Value::try_from(&self.config)?;
+            set_key(&mut effective, key, value)?;
+            self.config = effective.try_into()
+                .map_err(|e| anyhow::anyhow!("Invalid {}: {}", flag, e))?;
+            self.origins.insert(key.to_string(), ConfigOrigin::Flag(flag.to_string()));
+        }
+        Ok(())
+    }
+    
+    /// Every invalid value with the layer that set it.
+    pub fn issues(&self) -> Vec<(ConfigIssue, ConfigOrigin)> {
+        self.with_origins(self.config.validate())
+    }
+    
+    /// Invalid values for a display of the given size, with the layer that set them.
+    pub fn display_issues(&self, screen_width: u32, screen_height: u32) -> Vec<(ConfigIssue, ConfigOrigin)> {
+        self.with_origins(self.config.validate_for_display(screen_width, screen_height))
+    }
+    
+    /// Fail with one line per invalid value, naming the layer to fix.
+    pub fn validate(&self) -> Result<()> {
+        fail_on(self.issues())
+    }
+    
+    /// Like `validate`, for the values that only make sense on a particular display.
+    pub fn validate_for_display(&self, screen_width: u32, screen_height: u32) -> Result<()> {
+        fail_on(self.display_issues(screen_width, screen_height))
+    }
+    
+    fn with_origins(&self, issues: Vec<ConfigIssue>) -> Vec<(ConfigIssue, ConfigOrigin)> {
+        issues
+            .into_iter()
+            .map(|issue| {
+                let origin = self.origins.get(&issue.key).cloned().unwrap_or(ConfigOrigin::Default);
+                (issue, origin)
+            })
+            .collect()
+    }
+    
+    /// The effective value of a dotted key, or a whole section.
+    pub fn get(&self, key: &str) -> Result<toml::Value> {
+        let effective = toml::Value::try_from(&self.config)?;
+        let mut current = &effective;
+        for part in key.split('.') {
+            current = current.get(part).ok_or_else(|| unknown_key(key))?;
+        }
+        Ok(current.clone())
+    }
+    
+    /// The effective config as dotted keys with their values and origins.
+    pub fn entries(&self) -> Result<Vec<(String, toml::Value, ConfigOrigin)>> {
+        let effective = toml::Value::try_from(&self.config)?;
+        Ok(leaves(&effective)
+            .into_iter()
+            .filter(|(key, _)| key != "version")
+            .map(|(key, value)| {
+                let origin = self.origins.get(&key).cloned().unwrap_or(ConfigOrigin::Default);
+                (key, value.clone(), origin)
+            })
+            .collect())
+    }
+}
+
+/// Set `key` in the TOML file at `path`, creating it if needed. The value is
+/// type-checked against `Config` and the file must still load afterwards.
+pub fn set_in_file(path: &Path, key: &str, raw: &str) -> Result<toml::Value> {
+    let schema = schema()?;
+    let value = parse_value(key, raw, schema_leaf(&schema, key)?)?;
+    
+    let mut file_value = if path.exists() {
+        toml::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| anyhow::anyhow!("Invalid {:?}: {}", path, e))?
+    } else {
+        toml::Value::Table(toml::map::Map::new())
+    };
+    set_key(&mut file_value, key, value.clone())?;
+    check_file_value(&file_value)?;
+    
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, toml::to_string_pretty(&file_value)?)?;
+    Ok(value)
+}
+
+/// Check a whole config file: known keys, matching types, and values that
+/// pass `Config::validate` once merged over the defaults.
+pub fn check_file(contents: &str) -> Result<()> {
+    let file_value: toml::Value = toml::from_str(contents)?;
+    check_file_value(&file_value)
+}
+
+fn check_file_value(file_value: &toml::Value) -> Result<()> {
+    let schema = schema()?;
+    let mut merged = toml::Value::try_from(Config::default())?;
+    merge(&mut merged, file_value, &schema, &ConfigOrigin::Cli, &mut BTreeMap::new())?;
+    let issues = merged.try_into::<Config>()?.validate();
+    if !issues.is_empty() {
+        let lines: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+        return Err(anyhow::anyhow!("Invalid configuration:\n  {}", lines.join("\n  ")));
+    }
+    Ok(())
+}
+
+/// Where `config set --project` writes: the nearest project file, or a new one here.
+pub fn project_file_for_writing() -> Result<PathBuf> {
+    match find_project_file()? {
+        Some(path) => Ok(path),
+        None => Ok(std::env::current_dir()?.join(PROJECT_CONFIG_FILE)),
+    }
+}
+
+/// Format a value for display. Config floats are `f32`, so print them at that
+/// precision instead of the widened `f64` (0.7, not 0.699999988).
+pub fn display_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::Float(float) => format!("{:?}", *float as f32),
+        value => value.to_string(),
+    }
+}
+
+/// `zoom_grid.subdivision` -> `GUI_HELPER_ZOOM_GRID_SUBDIVISION`
+pub fn env_variable(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+}
+
+// Every settable key with a value of the right type. Optional settings are
+// absent from the serialized defaults, so they are filled in here.
+fn schema() -> Result<toml::Value> {
+    let config = Config {
+        retention: RetentionConfig {
+            keep: Some(0),
+            older_than: Some(String::new()),
+            max_size: Some(String::new()),
+        },
+        ..Config::default()
+    };
+    Ok(toml::Value::try_from(config)?)
+}
+
+fn schema_leaf<'a>(schema: &'a toml::Value, key: &str) -> Result<&'a toml::Value> {
+    let mut current = schema;
+    for part in key.split('.') {
+        current = current.get(part).ok_or_else(|| unknown_key(key))?;
+    }
+    
+    if current.is_table() || key == "version" {
+        return Err(anyhow::anyhow!("'{}' is not a settable value. Use e.g. zoom_grid.subdivision", key));
+    }
+    Ok(current)
+}
+
+// One line per invalid value, naming the layer to fix
+fn fail_on(issues: Vec<(ConfigIssue, ConfigOrigin)>) -> Result<()> {
+    if issues.is_empty() {
+        return Ok(());
+    }
+    
+    let mut message = String::from("Invalid configuration:");
+    for (issue, origin) in &issues {
+        message.push_str(&format!("\n  {} (from {})", issue, origin));
+    }
+    message.push_str("\nFix the values above, or run 'gui-helper config validate' for details");
+    Err(anyhow::anyhow!(message))
+}
+
+fn unknown_key(key: &str) -> anyhow::Error {
+    anyhow::anyhow!("Unknown config key '{}'. See 'gui-helper config show' for all keys", key)
+}
+
+// Parse a command-line or environment value into the type the schema expects
+fn parse_value(key: &str, raw: &str, leaf: &toml::Value) -> Result<toml::Value> {
+    let invalid = |expected: &str| anyhow::anyhow!("Invalid value '{}' for {}: expected {}", raw, key, expected);
+    
+    match leaf {
+        toml::Value::Integer(_) => raw.parse::<i64>().map(toml::Value::Integer).map_err(|_| invalid("an integer")),
+        toml::Value::Float(_) => raw.parse::<f64>().map(toml::Value::Float).map_err(|_| invalid("a number")),
+        toml::Value::Boolean(_) => match raw.to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(toml::Value::Boolean(true)),
+            "false" | "no" | "off" | "0" => Ok(toml::Value::Boolean(false)),
+            _ => Err(invalid("true or false")),
+        },
+        _ => Ok(toml::Value::String(raw.to_string())),
+    }
+}
+
+// Overlay every leaf of `layer` onto `merged`, checking keys and types against the schema
+fn merge(
+    merged: &mut toml::Value,
+    layer: &toml::Value,
+    schema: &toml::Value,
+    origin: &ConfigOrigin,
+    origins: &mut BTreeMap<String, ConfigOrigin>,
+) -> Result<()> {
+    for (key, value) in leaves(layer) {
+        if key == "version" {
+            continue;
+        }
+        
+        let leaf = match schema_leaf(schema, &key) {
+            Ok(leaf) => leaf,
+            Err(_) => {
+                eprintln!("⚠ Ignoring unknown config key '{}' from {}", key, origin);
+                continue;
+            }
+        };
+        
+        let value = match (leaf, value) {
+            (toml::Value::Float(_), toml::Value::Integer(integer)) => toml::Value::Float(*integer as f64),
+            (leaf, value) if leaf.same_type(value) => value.clone(),
+            (leaf, value) => {
+                return Err(anyhow::anyhow!(
+                    "Invalid {} in {}: expected {}, found {} ({})",
+                    key, origin, leaf.type_str(), value.type_str(), value
+                ));
+            }
+        };
+        
+        set_key(merged, &key, value)?;
+        origins.insert(key, origin.clone());
+    }
+    
+    Ok(())
+}
+
+fn set_key(root: &mut toml::Value, key: &str, value: toml::Value) -> Result<()> {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().ok_or_else(|| unknown_key(key))?;
+    
+    let mut current = root;
+    for part in parts {
+        let table = current.as_table_mut().ok_or_else(|| unknown_key(key))?;
+        current = table.entry(part).or_insert_with(|| toml::Value::Table(toml::map::Map::new()));
+    }
+    
+    current.as_table_mut()
+        .ok_or_else(|| unknown_key(key))?
+        .insert(last.to_string(), value);
+    Ok(())
+}
+
+// All non-table values as (dotted key, value), in file order
+fn leaves(value: &toml::Value) -> Vec<(String, &toml::Value)> {
+    let mut result = Vec::new();
+    if let Some(table) = value.as_table() {
+        for (name, child) in table {
+            if child.is_table() {
+                for (key, leaf) in leaves(child) {
+                    result.push((format!("{}.{}", name, key), leaf));
+                }
+            } else {
+                result.push((name.clone(), child));
+            }
+        }
+    }
+    result
+}
+
+fn find_project_file() -> Result<Option<PathBuf>> {
+    let current_dir = std::env::current_dir()?;
+    Ok(current_dir.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|path| path.is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn defaults() -> (toml::Value, BTreeMap<String, ConfigOrigin>) {
+        let merged = toml::Value::try_from(Config::default()).unwrap();
+        let origins = leaves(&merged).into_iter().map(|(key, _)| (key, ConfigOrigin::Default)).collect();
+        (merged, origins)
+    }
+    
+    fn layered(config: Config) -> LayeredConfig {
+        LayeredConfig {
+            config,
+            origins: BTreeMap::new(),
+            user_path: PathBuf::from("config.toml"),
+            project_path: None,
+        }
+    }
+    
+    #[test]
+    fn later_layers_win_and_record_their_origin() {
+        let schema = schema().unwrap();
+        let (mut merged, mut origins) = defaults();
+        let user = ConfigOrigin::User(PathBuf::from("config.toml"));
+        let project = ConfigOrigin::Project(PathBuf::from(PROJECT_CONFIG_FILE));
+        
+        let user_layer: toml::Value = toml::from_str("[zoom_grid]\npadding = 20\nsubdivision = 8\n").unwrap();
+        let project_layer: toml::Value = toml::from_str("[zoom_grid]\nsubdivision = 6\n").unwrap();
+        merge(&mut merged, &user_layer, &schema, &user, &mut origins).unwrap();
+        merge(&mut merged, &project_layer, &schema, &project, &mut origins).unwrap();
+        
+        let config: Config = merged.try_into().unwrap();
+        assert_eq!(config.zoom_grid.padding, 20);
+        assert_eq!(config.zoom_grid.subdivision, 6);
+        assert_eq!(origins["zoom_grid.padding"], user);
+        assert_eq!(origins["zoom_grid.subdivision"], project);
+        assert_eq!(origins["zoom_grid.thickness"], ConfigOrigin::Default);
+    }
+    
+    #[test]
+    fn merge_accepts_integers_for_floats_and_skips_unknown_keys() {
+        let schema = schema().unwrap();
+        let (mut merged, mut origins) = defaults();
+        let layer: toml::Value = toml::from_str("[zoom_grid]\nopacity = 1\nshade = 3\n").unwrap();
+        merge(&mut merged, &layer, &schema, &ConfigOrigin::Cli, &mut origins).unwrap();
+        
+        let config: Config = merged.try_into().unwrap();
+        assert_eq!(config.zoom_grid.opacity, 1.0);
+        assert!(!origins.contains_key("zoom_grid.shade"));
+    }
+    
+    #[test]
+    fn merge_rejects_values_of_the_wrong_type() {
+        let schema = schema().unwrap();
+        let (mut merged, mut origins) = defaults();
+        let layer: toml::Value = toml::from_str("[zoom_grid]\nsubdivision = \"many\"\n").unwrap();
+        
+        let error = merge(&mut merged, &layer, &schema, &ConfigOrigin::Cli, &mut origins).unwrap_err();
+        assert!(error.to_string().contains("zoom_grid.subdivision"));
+    }
+    
+    #[test]
+    fn optional_settings_are_in_the_schema() {
+        let schema = schema().unwrap();
+        assert!(schema_leaf(&schema, "retention.keep").unwrap().is_integer());
+        assert!(schema_leaf(&schema, "retention.older_than").unwrap().is_str());
+        assert!(schema_leaf(&schema, "zoom_grid").is_err());
+        assert!(schema_leaf(&schema, "version").is_err());
+        assert!(schema_leaf(&schema, "zoom_grid.nope").is_err());
+    }
+    
+    #[test]
+    fn parses_values_by_their_schema_type() {
+        let schema = schema().unwrap();
+        let parse = |key: &str, raw: &str| parse_value(key, raw, schema_leaf(&schema, key).unwrap());
+        
+        assert_eq!(parse("zoom_grid.subdivision", "6").unwrap(), toml::Value::Integer(6));
+        assert_eq!(parse("zoom_grid.opacity", "0.5").unwrap(), toml::Value::Float(0.5));
+        assert_eq!(parse("zoom_grid.show_numbers", "off").unwrap(), toml::Value::Boolean(false));
+        assert_eq!(parse("zoom_grid.color", "red").unwrap(), toml::Value::String("red".to_string()));
+        assert!(parse("zoom_grid.subdivision", "six").is_err());
+        assert!(parse("zoom_grid.show_numbers", "maybe").is_err());
+    }
+    
+    #[test]
+    fn env_variables_follow_the_dotted_key() {
+        assert_eq!(env_variable("zoom_grid.subdivision"), "GUI_HELPER_ZOOM_GRID_SUBDIVISION");
+    }
+    
+    #[test]
+    fn flags_override_the_config_and_are_named_in_issues() {
+        let mut layered = layered(Config::default());
+        layered.override_flag("zoom_grid.subdivision", "--subdivision", Some(toml::Value::Integer(0))).unwrap();
+        layered.override_flag("zoom_grid.padding", "--padding", None).unwrap();
+        
+        assert_eq!(layered.config.zoom_grid.subdivision, 0);
+        assert!(!layered.origins.contains_key("zoom_grid.padding"));
+        
+        let issues = layered.issues();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].0.key, "zoom_grid.subdivision");
+        assert_eq!(issues[0].1, ConfigOrigin::Flag("--subdivision".to_string()));
+        assert!(layered.validate().unwrap_err().to_string().contains("(from --subdivision)"));
+    }
+    
+    #[test]
+    fn grid_size_flag_is_checked_against_the_display() {
+        let mut layered = layered(Config::default());
+        layered.override_flag("overview_grid.square_size", "--grid-size", Some(toml::Value::Integer(2000))).unwrap();
+        
+        // Within the static range, but taller than a 1080 px screen
+        assert!(layered.validate().is_ok());
+        let issues = layered.display_issues(1920, 1080);
+        assert_eq!(issues[0].1, ConfigOrigin::Flag("--grid-size".to_string()));
+        assert!(layered.validate_for_display(1920, 1080).unwrap_err().to_string().contains("(from --grid-size)"));
+    }
+    
+    #[test]
+    fn get_reads_keys_and_sections() {
+        let layered = layered(Config::default());
+        assert_eq!(layered.get("zoom_grid.subdivision").unwrap(), toml::Value::Integer(Config::default().zoom_grid.subdivision as i64));
+        assert!(layered.get("zoom_grid").unwrap().is_table());
+        assert!(layered.get("zoom_grid.nope").is_err());
+    }
+    
+    #[test]
+    fn check_file_rejects_out_of_range_values() {
+        assert!(check_file("[zoom_grid]\nsubdivision = 6\n").is_ok());
+        assert!(check_file("[zoom_grid]\nsubdivision = 0\n").is_err());
+    }
+}
@@ -1,17 +1,60 @@
+pub mod layers;
+pub mod validate;
+
+pub use layers::{ConfigOrigin, LayeredConfig};
+pub use validate::ConfigIssue;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Current layout of `config.toml`. Bump it and add a step to `migrate` when
+/// a field is renamed or moved; purely additive fields only need a default.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Error for a config or session file written by a newer gui-helper. Such files
+/// are refused and left as they are, not backed up and replaced.
+#[derive(Debug)]
+pub struct NewerVersion {
+    pub file: String,  // "config" or "session"
+    pub version: u32,
+    pub supported: u32,
+}
+
+impl fmt::Display for NewerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} version {} is newer than this gui-helper supports ({}); upgrade gui-helper to use it",
+               self.file, self.version, self.supported)
+    }
+}
+
+impl std::error::Error for NewerVersion {}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    #[serde(default)]
+    pub version: u32,    // Missing in files written before versioning (0)
     pub overview_grid: OverviewGridConfig,
     pub zoom_grid: ZoomGridConfig,
     pub display: DisplayConfig,
     pub automation: AutomationConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub settle: SettleConfig,
+    #[serde(default)]
+    pub wait: WaitConfig,
+    #[serde(default)]
+    pub ocr: OcrConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OverviewGridConfig {
     pub square_size: u32,
     pub color: String,
@@ -19,9 +62,17 @@ pub struct OverviewGridConfig {
     pub opacity: f32,
     pub show_numbers: bool,
     pub font_size: u32,
+    pub theme: GridTheme,         // Preset colors; `custom` uses the colors here
+    pub label_background: String, // Box behind square labels ("transparent" for none)
+    pub text_outline: String,     // Outline around label text ("none" to disable)
+    pub contrast: ContrastMode,   // How line colors adapt to the captured image
+    pub line_style: LineStyle,
+    pub antialias: bool,          // Smooth line edges that fall between pixels on scaled captures
+    pub highlight: String,        // Shade for the zoomed square in selection images
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ZoomGridConfig {
     pub padding: u32,
     pub subdivision: u32,
@@ -30,71 +81,326 @@ pub struct ZoomGridConfig {
     pub opacity: f32,
     pub show_numbers: bool,
     pub font_size: u32,
+    pub theme: GridTheme,         // Preset colors; `custom` uses the colors here
+    pub label_background: String, // Box behind square labels ("transparent" for none)
+    pub text_outline: String,     // Outline around label text ("none" to disable)
+    pub contrast: ContrastMode,   // How line colors adapt to the captured image
+    pub line_style: LineStyle,
+    pub antialias: bool,          // Smooth line edges that fall between pixels on scaled captures
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DisplayConfig {
     pub duration: u32,
     pub output_format: String,
     pub save_screenshots: bool,
     pub screenshot_dir: String,
+    pub draw_grid: bool,          // Draw the grid and labels into overview/zoom screenshots
+    pub click_receipts: bool,     // Save a crop around each click target (needs save_screenshots)
+    pub keep_clean_copy: bool,    // Also save a lossless grid-free copy for `template save` to crop from
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AutomationConfig {
     pub click_delay: u64,
     pub double_click: bool,
+    pub verify_delay: u64,     // ms to wait after a click before `--verify` captures again
+    pub change_threshold: f64, // Fraction of the area that must change to count as a reaction
+}
+
+/// Automatic screenshot pruning after each capture. Unset limits are ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    pub keep: Option<usize>,        // Keep at most this many screenshots
+    pub older_than: Option<String>, // Delete screenshots older than e.g. "7d"
+    pub max_size: Option<String>,   // Cap the directory at e.g. "1G"
+}
+
+/// Session lifetime and checks that the screen still matches the stored grid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub ttl_minutes: u64,                // 0 keeps sessions until cleared
+    pub stale_screen: StaleScreenPolicy, // What `click` does when the screen changed
+    pub max_hash_distance: u32,          // Differing hash bits (of 64) still counted as a match
+}
+
+/// When the screen counts as settled, for `overview --settle` and `wait-stable`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SettleConfig {
+    pub interval: u64,  // ms between captures
+    pub frames: u32,    // Consecutive quiet comparisons needed
+    pub threshold: f64, // Fraction of pixels that may change in a quiet comparison
+    pub timeout: u64,   // ms to wait before giving up
+}
+
+/// Polling for `wait-change` and `wait-pixel`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WaitConfig {
+    pub interval: u64, // ms between captures
+    pub timeout: u64,  // ms before giving up (exit code 3)
+}
+
+/// Text recognition for `ocr` and `click --text`, run by the local `tesseract`
+/// when built with the `ocr` feature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OcrConfig {
+    pub command: String,     // tesseract executable, looked up on PATH
+    pub language: String,    // Installed tesseract language(s), e.g. "eng" or "eng+deu"
+    pub page_mode: u32,      // tesseract --psm; 11 finds scattered text like UI labels
+    pub upscale: f32,        // Resize factor before recognition; small UI text reads better enlarged
+    pub min_confidence: f32, // Words below this (0-100) are dropped
+}
+
+/// Color presets for a grid level. Colors accept CSS names, `#rrggbb`,
+/// `#rrggbbaa` and `rgb()`; see `overlay::Color::parse`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GridTheme {
+    #[default]
+    Custom,
+    HighContrast,
+    ColorblindSafe,
+    Dark,
+    Light,
+}
+
+/// Line colors when the grid is drawn into a screenshot. Labels are kept
+/// readable (WCAG AA contrast) in every mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContrastMode {
+    #[default]
+    Fixed,  // The configured or theme color everywhere
+    Auto,   // Black or white per line segment, whichever stands out from the pixels under it
+    Invert, // The inverse of the average color under each segment
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StaleScreenPolicy {
+    Warn,
+    Refuse,
+    Ignore,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            ttl_minutes: 60,
+            stale_screen: StaleScreenPolicy::Warn,
+            max_hash_distance: 10,
+        }
+    }
+}
+
+impl Default for SettleConfig {
+    fn default() -> Self {
+        Self {
+            interval: 100,
+            frames: 3,
+            threshold: 0.001,
+            timeout: 5000,
+        }
+    }
+}
+
+impl Default for WaitConfig {
+    fn default() -> Self {
+        Self {
+            interval: 250,
+            timeout: 10_000,
+        }
+    }
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        Self {
+            command: "tesseract".to_string(),
+            language: "eng".to_string(),
+            page_mode: 11,
+            upscale: 2.0,
+            min_confidence: 60.0,
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            overview_grid: OverviewGridConfig {
-                square_size: 100,
-                color: "red".to_string(),
-                thickness: 2,
-                opacity: 0.7,
-                show_numbers: true,
-                font_size: 16,
-            },
-            zoom_grid: ZoomGridConfig {
-                padding: 50,
-                subdivision: 10,
-                color: "blue".to_string(),
-                thickness: 1,
-                opacity: 0.8,
-                show_numbers: true,
-                font_size: 12,
-            },
-            display: DisplayConfig {
-                duration: 3,
-                output_format: "png".to_string(),
-                save_screenshots: true,
-                screenshot_dir: "./screenshots".to_string(),
-            },
-            automation: AutomationConfig {
-                click_delay: 100,
-                double_click: false,
-            },
+            version: CONFIG_VERSION,
+            overview_grid: OverviewGridConfig::default(),
+            zoom_grid: ZoomGridConfig::default(),
+            display: DisplayConfig::default(),
+            automation: AutomationConfig::default(),
+            retention: RetentionConfig::default(),
+            session: SessionConfig::default(),
+            settle: SettleConfig::default(),
+            wait: WaitConfig::default(),
+            ocr: OcrConfig::default(),
+        }
+    }
+}
+
+impl Default for OverviewGridConfig {
+    fn default() -> Self {
+        Self {
+            square_size: 100,
+            color: "red".to_string(),
+            thickness: 2,
+            opacity: 0.7,
+            show_numbers: true,
+            font_size: 16,
+            theme: GridTheme::Custom,
+            label_background: "transparent".to_string(),
+            text_outline: "none".to_string(),
+            contrast: ContrastMode::Fixed,
+            line_style: LineStyle::Solid,
+            antialias: false,
+            highlight: "rgba(255, 215, 0, 0.35)".to_string(),
+        }
+    }
+}
+
+impl Default for ZoomGridConfig {
+    fn default() -> Self {
+        Self {
+            padding: 50,
+            subdivision: 10,
+            color: "blue".to_string(),
+            thickness: 1,
+            opacity: 0.8,
+            show_numbers: true,
+            font_size: 12,
+            theme: GridTheme::Custom,
+            label_background: "transparent".to_string(),
+            text_outline: "none".to_string(),
+            contrast: ContrastMode::Fixed,
+            line_style: LineStyle::Solid,
+            antialias: false,
+        }
+    }
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            duration: 3,
+            output_format: "png".to_string(),
+            save_screenshots: true,
+            screenshot_dir: "./screenshots".to_string(),
+            draw_grid: true,
+            click_receipts: true,
+            keep_clean_copy: false,
+        }
+    }
+}
+
+impl Default for AutomationConfig {
+    fn default() -> Self {
+        Self {
+            click_delay: 100,
+            double_click: false,
+            verify_delay: 500,
+            change_threshold: 0.001,
         }
     }
 }
 
 impl Config {
-    pub fn load() -> Result<Self> {
+    /// Load the user config file as written, migrated to the current layout.
+    /// Only the keys present in the file are returned; see `LayeredConfig` for
+    /// the effective configuration.
+    pub fn load_user_file() -> Result<toml::Value> {
         let config_path = Self::config_path()?;
         
-        if config_path.exists() {
-            let contents = fs::read_to_string(&config_path)?;
-            let config: Config = toml::from_str(&contents)?;
-            Ok(config)
-        } else {
+        if !config_path.exists() {
             let config = Config::default();
             config.save()?;
-            Ok(config)
+            return Ok(toml::Value::try_from(config)?);
+        }
+        
+        let contents = fs::read_to_string(&config_path)?;
+        match Self::parse(&contents) {
+            Ok((value, CONFIG_VERSION)) => Ok(value),
+            Ok((value, version)) => {
+                // Keep the old file around, then store it in the current layout
+                let backup_path = Self::backup(&config_path, &format!("v{}", version))?;
+                fs::write(&config_path, toml::to_string_pretty(&value)?)?;
+                eprintln!("⚠ Upgraded {:?} to config version {} (previous file saved as {:?})",
+                          config_path, CONFIG_VERSION, backup_path);
+                Ok(value)
+            }
+            Err(e) if e.is::<NewerVersion>() => {
+                Err(anyhow::anyhow!("Could not read {:?}: {}", config_path, e))
+            }
+            Err(e) => {
+                // A broken config must not block every command, `config --reset` included
+                let backup_path = Self::backup(&config_path, "invalid")?;
+                let config = Config::default();
+                config.save()?;
+                eprintln!("⚠ Could not read {:?}: {}", config_path, e);
+                eprintln!("⚠ Using defaults; the old file was saved as {:?}", backup_path);
+                Ok(toml::Value::try_from(config)?)
+            }
         }
     }
     
+    /// Parse a config file of any known version into the current layout.
+    /// Also returns the version the file was written with. Fails with
+    /// `NewerVersion` for files from a newer gui-helper.
+    pub fn parse(contents: &str) -> Result<(toml::Value, u32)> {
+        let mut value: toml::Value = toml::from_str(contents)?;
+        let version = value.get("version")
+            .and_then(|version| version.as_integer())
+            .unwrap_or(0) as u32;
+        
+        if version > CONFIG_VERSION {
+            return Err(NewerVersion { file: "config".to_string(), version, supported: CONFIG_VERSION }.into());
+        }
+        
+        Self::migrate(&mut value, version);
+        
+        // Type errors surface here rather than later in the merged config
+        value.clone().try_into::<Config>()?;
+        Ok((value, version))
+    }
+    
+    // One step per version; each step upgrades `from` to `from + 1`
+    fn migrate(value: &mut toml::Value, from: u32) {
+        for version in from..CONFIG_VERSION {
+            if version == 0 {
+                // Unversioned files: sections added since ([retention], [session])
+                // are filled from defaults by serde, so only the version is stamped.
+                if let Some(table) = value.as_table_mut() {
+                    table.insert("version".to_string(), toml::Value::Integer(1));
+                }
+            }
+        }
+    }
+    
+    fn backup(config_path: &Path, label: &str) -> Result<PathBuf> {
+        let timestamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+        let backup_path = config_path.with_extension(format!("toml.{}-{}.bak", label, timestamp));
+        fs::copy(config_path, &backup_path)?;
+        Ok(backup_path)
+    }
+    
     pub fn save(&self) -> Result<()> {
         let config_path = Self::config_path()?;
         
@@ -107,15 +413,87 @@ impl Config {
         Ok(())
     }
     
-    fn config_path() -> Result<PathBuf> {
+    pub fn config_path() -> Result<PathBuf> {
         let config_dir = dirs::config_dir()
             .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?;
         Ok(config_dir.join("gui-helper").join("config.toml"))
     }
     
+    /// Resolve `display.screenshot_dir`, expanding `~` and environment variables.
+    /// Relative paths are taken relative to the current directory, so each project
+    /// gets its own screenshots folder. The result is absolute, because paths
+    /// stored in sessions must still work from another directory.
+    pub fn screenshots_dir(&self) -> Result<PathBuf> {
+        let expanded = shellexpand::full(&self.display.screenshot_dir)
+            .map_err(|e| anyhow::anyhow!("Invalid display.screenshot_dir '{}': {}", self.display.screenshot_dir, e))?;
+        std::path::absolute(expanded.as_ref())
+            .map_err(|e| anyhow::anyhow!("Invalid display.screenshot_dir '{}': {}", self.display.screenshot_dir, e))
+    }
+    
     pub fn data_dir() -> Result<PathBuf> {
+        #[cfg(test)]
+        if let Some(data_dir) = TEST_DATA_DIR.with(|data_dir| data_dir.borrow().clone()) {
+            return Ok(data_dir);
+        }
+        
         let data_dir = dirs::data_local_dir()
             .ok_or_else(|| anyhow::anyhow!("Could not find local data directory"))?;
         Ok(data_dir.join("gui-helper"))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+thread_local! {
+    // Tests that store sessions point this at a temporary directory of their own
+    pub static TEST_DATA_DIR: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn screenshots_dir_is_absolute() {
+        let mut config = Config::default();
+        config.display.screenshot_dir = "./screenshots".to_string();
+        
+        let dir = config.screenshots_dir().unwrap();
+        assert!(dir.is_absolute());
+        assert_eq!(dir, std::env::current_dir().unwrap().join("screenshots"));
+    }
+    
+    #[test]
+    fn parse_stamps_the_version_on_unversioned_files() {
+        let (value, version) = Config::parse("[overview_grid]\nsquare_size = 80\n").unwrap();
+        
+        assert_eq!(version, 0);
+        assert_eq!(value.get("version").and_then(|version| version.as_integer()), Some(CONFIG_VERSION as i64));
+        let config: Config = value.try_into().unwrap();
+        assert_eq!(config.overview_grid.square_size, 80);
+        assert_eq!(config.zoom_grid.subdivision, Config::default().zoom_grid.subdivision);
+    }
+    
+    #[test]
+    fn parse_keeps_current_files_as_they_are() {
+        let contents = toml::to_string_pretty(&Config::default()).unwrap();
+        let (value, version) = Config::parse(&contents).unwrap();
+        
+        assert_eq!(version, CONFIG_VERSION);
+        assert_eq!(value, toml::Value::try_from(Config::default()).unwrap());
+    }
+    
+    #[test]
+    fn parse_rejects_type_errors() {
+        assert!(Config::parse("version = 1\n[overview_grid]\nsquare_size = \"big\"\n").is_err());
+        assert!(Config::parse("[overview_grid").is_err());
+    }
+    
+    #[test]
+    fn parse_refuses_newer_versions() {
+        let error = Config::parse(&format!("version = {}\n", CONFIG_VERSION + 1)).unwrap_err();
+        
+        let newer = error.downcast_ref::<NewerVersion>().unwrap();
+        assert_eq!((newer.version, newer.supported), (CONFIG_VERSION + 1, CONFIG_VERSION));
+        assert!(error.to_string().contains("newer than this gui-helper supports"));
+    }
+}
@@ -0,0 +1,253 @@
+use crate::config::Config;
+use crate::overlay::Color;
+use crate::screenshot::{parse_age, parse_byte_size};
+use std::fmt;
+
+/// Encodings supported for screenshots.
+pub const OUTPUT_FORMATS: &[&str] = &["png", "jpeg", "jpg", "webp"];
+
+/// A config value that deserialized fine but can't be used.
+#[derive(Debug, Clone)]
+pub struct ConfigIssue {
+    pub key: String,     // Dotted key, e.g. "zoom_grid.subdivision"
+    pub value: String,   // The rejected value as written
+    pub allowed: String, // What would have been accepted
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}: {}", self.key, self.value, self.allowed)
+    }
+}
+
+impl Config {
+    /// Check every value against the range the commands can work with.
+    /// Returns all problems at once so they can be fixed in one go.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        
+        let overview = &self.overview_grid;
+        check_range(&mut issues, "overview_grid.square_size", overview.square_size, 10, 2000);
+        check_color(&mut issues, "overview_grid.color", &overview.color);
+        check_color(&mut issues, "overview_grid.label_background", &overview.label_background);
+        check_color(&mut issues, "overview_grid.text_outline", &overview.text_outline);
+        check_range(&mut issues, "overview_grid.thickness", overview.thickness, 1, 20);
+        check_fraction(&mut issues, "overview_grid.opacity", overview.opacity);
+        check_range(&mut issues, "overview_grid.font_size", overview.font_size, 6, 72);
+        check_color(&mut issues, "overview_grid.highlight", &overview.highlight);
+        
+        let zoom = &self.zoom_grid;
+        check_range(&mut issues, "zoom_grid.padding", zoom.padding, 0, 1000);
+        check_range(&mut issues, "zoom_grid.subdivision", zoom.subdivision, 1, 50);
+        check_color(&mut issues, "zoom_grid.color", &zoom.color);
+        check_color(&mut issues, "zoom_grid.label_background", &zoom.label_background);
+        check_color(&mut issues, "zoom_grid.text_outline", &zoom.text_outline);
+        check_range(&mut issues, "zoom_grid.thickness", zoom.thickness, 1, 20);
+        check_fraction(&mut issues, "zoom_grid.opacity", zoom.opacity);
+        check_range(&mut issues, "zoom_grid.font_size", zoom.font_size, 6, 72);
+        // A zoom area at the screen edge loses the padding on that side
+        if zoom.subdivision > overview.square_size.saturating_add(zoom.padding) {
+            issues.push(issue(
+                "zoom_grid.subdivision",
+                &zoom.subdivision.to_string(),
+                &format!("must be at most overview_grid.square_size + zoom_grid.padding ({}), or zoom squares are 0 px wide",
+                         overview.square_size.saturating_add(zoom.padding)),
+            ));
+        }
+        
+        check_range(&mut issues, "display.duration", self.display.duration, 0, 60);
+        check_format(&mut issues, "display.output_format", &self.display.output_format);
+        if self.display.screenshot_dir.trim().is_empty() {
+            issues.push(issue("display.screenshot_dir", "\"\"", "must not be empty"));
+        } else if shellexpand::full(&self.display.screenshot_dir).is_err() {
+            issues.push(issue("display.screenshot_dir", &format!("{:?}", self.display.screenshot_dir), "uses an unset environment variable"));
+        }
+        
+        check_range(&mut issues, "automation.click_delay", self.automation.click_delay, 0, 10_000);
+        check_range(&mut issues, "automation.verify_delay", self.automation.verify_delay, 0, 10_000);
+        check_fraction(&mut issues, "automation.change_threshold", self.automation.change_threshold);
+        
+        if let Some(older_than) = &self.retention.older_than {
+            if parse_age(older_than).is_err() {
+                issues.push(issue("retention.older_than", &format!("{:?}", older_than), "expected an age like 30m, 12h, 7d or 2w"));
+            }
+        }
+        if let Some(max_size) = &self.retention.max_size {
+            if parse_byte_size(max_size).is_err() {
+                issues.push(issue("retention.max_size", &format!("{:?}", max_size), "expected a size like 500k, 2M or 1G"));
+            }
+        }
+        
+        check_range(&mut issues, "session.max_hash_distance", self.session.max_hash_distance, 0, 64);
+        
+        check_range(&mut issues, "settle.interval", self.settle.interval, 10, 10_000);
+        check_range(&mut issues, "settle.frames", self.settle.frames, 1, 100);
+        check_fraction(&mut issues, "settle.threshold", self.settle.threshold);
+        check_range(&mut issues, "settle.timeout", self.settle.timeout, 0, 600_000);
+        check_range(&mut issues, "wait.interval", self.wait.interval, 10, 10_000);
+        check_range(&mut issues, "wait.timeout", self.wait.timeout, 0, 3_600_000);
+        
+        if self.ocr.command.trim().is_empty() {
+            issues.push(issue("ocr.command", "\"\"", "must not be empty"));
+        }
+        let language_valid = self.ocr.language.split('+')
+            .all(|language| !language.is_empty() && language.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+        if !language_valid {
+            issues.push(issue("ocr.language", &format!("{:?}", self.ocr.language), "expected tesseract language codes like eng or eng+deu"));
+        }
+        check_range(&mut issues, "ocr.page_mode", self.ocr.page_mode, 0, 13);
+        check_range(&mut issues, "ocr.upscale", self.ocr.upscale, 0.5, 4.0);
+        check_range(&mut issues, "ocr.min_confidence", self.ocr.min_confidence, 0.0, 100.0);
+        
+        issues
+    }
+    
+    /// Check the values that depend on the display the grid is drawn on.
+    /// Overview rows are labelled A-Z, so there must be 1 to 26 of them.
+    pub fn validate_for_display(&self, screen_width: u32, screen_height: u32) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        
+        let min = screen_height / 27 + 1;
+        let max = screen_width.min(screen_height);
+        let square_size = self.overview_grid.square_size;
+        if min <= max && (square_size < min || square_size > max) {
+            issues.push(issue(
+                "overview_grid.square_size",
+                &square_size.to_string(),
+                &format!("must be between {} and {} on this {}x{} display, for 1 to 26 rows (A-Z)", min, max, screen_width, screen_height),
+            ));
+        }
+        
+        issues
+    }
+}
+
+fn issue(key: &str, value: &str, allowed: &str) -> ConfigIssue {
+    ConfigIssue {
+        key: key.to_string(),
+        value: value.to_string(),
+        allowed: allowed.to_string(),
+    }
+}
+
+fn check_range<T: PartialOrd + fmt::Display>(issues: &mut Vec<ConfigIssue>, key: &str, value: T, min: T, max: T) {
+    if value < min || value > max {
+        issues.push(issue(key, &value.to_string(), &format!("must be between {} and {}", min, max)));
+    }
+}
+
+fn check_fraction<T: Copy + Into<f64> + fmt::Debug>(issues: &mut Vec<ConfigIssue>, key: &str, value: T) {
+    // NaN fails both comparisons, so test for the valid range instead
+    if !(0.0..=1.0).contains(&value.into()) {
+        issues.push(issue(key, &format!("{:?}", value), "must be between 0.0 and 1.0"));
+    }
+}
+
+fn check_color(issues: &mut Vec<ConfigIssue>, key: &str, color: &str) {
+    if Color::parse(color).is_err() {
+        issues.push(issue(key, &format!("{:?}", color), "expected a CSS color name, #rrggbb, #rrggbbaa or rgb(r, g, b)"));
+    }
+}
+
+fn check_format(issues: &mut Vec<ConfigIssue>, key: &str, format: &str) {
+    if !OUTPUT_FORMATS.contains(&format.to_lowercase().as_str()) {
+        issues.push(issue(key, &format!("{:?}", format), &format!("must be one of {}", OUTPUT_FORMATS.join(", "))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn keys(issues: &[ConfigIssue]) -> Vec<&str> {
+        issues.iter().map(|issue| issue.key.as_str()).collect()
+    }
+    
+    #[test]
+    fn defaults_are_valid() {
+        let config = Config::default();
+        assert!(config.validate().is_empty());
+        assert!(config.validate_for_display(1920, 1080).is_empty());
+    }
+    
+    #[test]
+    fn out_of_range_values_are_reported_together() {
+        let mut config = Config::default();
+        config.overview_grid.square_size = 5;
+        config.zoom_grid.thickness = 0;
+        config.settle.frames = 101;
+        
+        let issues = config.validate();
+        assert_eq!(keys(&issues), ["overview_grid.square_size", "zoom_grid.thickness", "settle.frames"]);
+        assert_eq!(issues[0].value, "5");
+        assert_eq!(issues[0].allowed, "must be between 10 and 2000");
+    }
+    
+    #[test]
+    fn range_bounds_are_inclusive() {
+        let mut config = Config::default();
+        config.zoom_grid.subdivision = 1;
+        config.zoom_grid.padding = 1000;
+        config.display.duration = 0;
+        assert!(config.validate().is_empty());
+        
+        config.zoom_grid.subdivision = 51;
+        assert_eq!(keys(&config.validate()), ["zoom_grid.subdivision"]);
+    }
+    
+    #[test]
+    fn fractions_reject_nan() {
+        let mut config = Config::default();
+        config.overview_grid.opacity = f32::NAN;
+        config.automation.change_threshold = 1.5;
+        assert_eq!(keys(&config.validate()), ["overview_grid.opacity", "automation.change_threshold"]);
+    }
+    
+    #[test]
+    fn colors_formats_and_languages_are_checked() {
+        let mut config = Config::default();
+        config.zoom_grid.color = "not-a-color".to_string();
+        config.display.output_format = "gif".to_string();
+        config.ocr.language = "eng+".to_string();
+        assert_eq!(keys(&config.validate()), ["zoom_grid.color", "display.output_format", "ocr.language"]);
+    }
+    
+    #[test]
+    fn subdivision_must_fit_the_zoom_area_at_the_screen_edge() {
+        let mut config = Config::default();
+        config.overview_grid.square_size = 20;
+        config.zoom_grid.padding = 10;
+        config.zoom_grid.subdivision = 30;
+        assert!(config.validate().is_empty());
+        
+        config.zoom_grid.subdivision = 31;
+        let issues = config.validate();
+        assert_eq!(keys(&issues), ["zoom_grid.subdivision"]);
+        assert!(issues[0].allowed.contains("(30)"));
+    }
+    
+    #[test]
+    fn square_size_must_give_one_to_26_rows() {
+        let mut config = Config::default();
+        
+        // 1080 / 41 = 26 rows, 1080 / 40 = 27
+        config.overview_grid.square_size = 41;
+        assert!(config.validate_for_display(1920, 1080).is_empty());
+        config.overview_grid.square_size = 40;
+        assert_eq!(keys(&config.validate_for_display(1920, 1080)), ["overview_grid.square_size"]);
+        
+        // Taller than the screen leaves no rows at all
+        config.overview_grid.square_size = 1080;
+        assert!(config.validate_for_display(1920, 1080).is_empty());
+        config.overview_grid.square_size = 1081;
+        let issues = config.validate_for_display(1920, 1080);
+        assert_eq!(issues[0].allowed, "must be between 41 and 1080 on this 1920x1080 display, for 1 to 26 rows (A-Z)");
+    }
+    
+    #[test]
+    fn square_size_must_leave_a_column_on_a_portrait_display() {
+        let mut config = Config::default();
+        config.overview_grid.square_size = 1000;
+        assert_eq!(keys(&config.validate_for_display(800, 1280)), ["overview_grid.square_size"]);
+    }
+}
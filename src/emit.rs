@@ -0,0 +1,65 @@
+use anyhow::Result;
+use base64::Engine;
+use clap::ValueEnum;
+use crate::grid::{CapturedImage, OverviewSquare, ScreenInfo, SessionData, ZoomArea, ZoomSquare};
+use crate::screenshot::{EncodedImage, ScreenshotCapture};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EmitFormat {
+    /// One JSON document with the base64 image, grid and session summary
+    Json,
+}
+
+/// Everything an agent needs from `overview`/`zoom`, without touching the disk.
+#[derive(Debug, Serialize)]
+pub struct EmitDocument<'a> {
+    pub command: &'a str,
+    pub mime_type: &'static str,
+    pub image_base64: String,
+    pub image: &'a CapturedImage,
+    pub overview_grid: &'a [OverviewSquare],
+    pub zoom_area: Option<&'a ZoomArea>,
+    pub zoom_grid: &'a [ZoomSquare],
+    pub session: SessionSummary<'a>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionSummary<'a> {
+    pub screen_info: &'a ScreenInfo,
+    pub selected_square: Option<&'a str>,
+    pub overview_squares: usize,
+    pub zoom_squares: usize,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> EmitDocument<'a> {
+    pub fn new(
+        command: &'a str,
+        encoded: &EncodedImage,
+        image: &'a CapturedImage,
+        session_data: &'a SessionData,
+    ) -> Result<Self> {
+        Ok(Self {
+            command,
+            mime_type: ScreenshotCapture::mime_type(&encoded.format)?,
+            image_base64: base64::engine::general_purpose::STANDARD.encode(&encoded.bytes),
+            image,
+            overview_grid: &session_data.overview_grid,
+            zoom_area: session_data.zoom_area.as_ref(),
+            zoom_grid: &session_data.zoom_grid,
+            session: SessionSummary {
+                screen_info: &session_data.screen_info,
+                selected_square: session_data.selected_square.as_deref(),
+                overview_squares: session_data.overview_grid.len(),
+                zoom_squares: session_data.zoom_grid.len(),
+                timestamp: session_data.timestamp,
+            },
+        })
+    }
+    
+    pub fn print(&self) -> Result<()> {
+        println!("{}", serde_json::to_string(self)?);
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
+use crate::screenshot::ScreenFingerprint;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OverviewSquare {
@@ -49,14 +51,138 @@ pub struct ScreenInfo {
     pub cols: usize,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedImage {
+    pub path: Option<PathBuf>, // Where the image was saved, if anywhere
+    pub format: String,        // Encoded format ("png", "jpeg", ...)
+    pub width: u32,            // Image width in image pixels
+    pub height: u32,           // Image height in image pixels
+    pub origin_x: u32,         // Screen x of the image's top-left corner
+    pub origin_y: u32,         // Screen y of the image's top-left corner
+    pub scale: f64,            // Image pixels per screen pixel
+    #[serde(default)]
+    pub grid_drawn: bool,      // Grid lines and labels were drawn into the image
+    #[serde(default)]
+    pub clean_path: Option<PathBuf>, // Lossless copy without the grid, possibly larger, if `path` has one or is JPEG
+}
+
+/// Current layout of session files. Bump it and add a step to
+/// `SessionManager::migrate_session` when a field is renamed or moved.
+pub const SESSION_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionData {
+    #[serde(default)]
+    pub version: u32,                    // Missing in files written before versioning (0)
     pub overview_grid: Vec<OverviewSquare>,
     pub selected_square: Option<String>,
     pub zoom_area: Option<ZoomArea>,
     pub zoom_grid: Vec<ZoomSquare>,
     pub screen_info: ScreenInfo,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub overview_image: Option<CapturedImage>,
+    #[serde(default)]
+    pub zoom_image: Option<CapturedImage>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub fingerprint: Option<ScreenFingerprint>,
+    #[serde(default)]
+    pub history: Vec<GridStep>,         // Append-only; `session back` only moves `current_step`
+    #[serde(default)]
+    pub current_step: Option<usize>,
+}
+
+/// Snapshot of the grid state after one `overview` or `zoom`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridStep {
+    pub step: usize,
+    pub previous: Option<usize>,   // Step this one was taken from
+    pub command: String,           // "overview" or "zoom B5"
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub selected_square: Option<String>,
+    pub zoom_area: Option<ZoomArea>,
+    pub zoom_grid: Vec<ZoomSquare>,
+    pub zoom_image: Option<CapturedImage>,
+    pub zoom_hash: Option<u64>,
+    pub screenshot: Option<PathBuf>, // Image captured at this step, if it was saved
+}
+
+impl CapturedImage {
+    /// Describe an image that shows the screen area starting at
+    /// (`origin_x`, `origin_y`) and `area_width` screen pixels wide.
+    pub fn new(path: Option<PathBuf>, format: &str, width: u32, height: u32, origin_x: u32, origin_y: u32, area_width: u32) -> Self {
+        Self {
+            path,
+            format: format.to_string(),
+            width,
+            height,
+            origin_x,
+            origin_y,
+            scale: width as f64 / area_width.max(1) as f64,
+            grid_drawn: false,
+            clean_path: None,
+        }
+    }
+    
+    pub fn with_grid_drawn(mut self, grid_drawn: bool) -> Self {
+        self.grid_drawn = grid_drawn;
+        self
+    }
+    
+    pub fn with_clean_path(mut self, clean_path: Option<PathBuf>) -> Self {
+        self.clean_path = clean_path;
+        self
+    }
+    
+    /// Map a pixel position on the image back to absolute screen coordinates.
+    pub fn to_screen(&self, image_x: f64, image_y: f64) -> (u32, u32) {
+        let x = self.origin_x as f64 + image_x / self.scale;
+        let y = self.origin_y as f64 + image_y / self.scale;
+        (x.round().max(0.0) as u32, y.round().max(0.0) as u32)
+    }
+}
+
+impl SessionData {
+    /// Append the current grid state to the history and make it the current step.
+    pub fn record_step(&mut self, command: &str) {
+        let step = self.history.len();
+        let screenshot = match &self.zoom_image {
+            Some(zoom_image) => zoom_image.path.clone(),
+            None => self.overview_image.as_ref().and_then(|image| image.path.clone()),
+        };
+        
+        self.history.push(GridStep {
+            step,
+            previous: self.current_step,
+            command: command.to_string(),
+            timestamp: chrono::Utc::now(),
+            selected_square: self.selected_square.clone(),
+            zoom_area: self.zoom_area.clone(),
+            zoom_grid: self.zoom_grid.clone(),
+            zoom_image: self.zoom_image.clone(),
+            zoom_hash: self.fingerprint.as_ref().and_then(|fingerprint| fingerprint.zoom_hash),
+            screenshot,
+        });
+        self.current_step = Some(step);
+    }
+    
+    /// Restore the grid state of `step` without touching the history.
+    pub fn restore_step(&mut self, step: usize) -> Option<&GridStep> {
+        let grid_step = self.history.get(step)?.clone();
+        
+        self.selected_square = grid_step.selected_square;
+        self.zoom_area = grid_step.zoom_area;
+        self.zoom_grid = grid_step.zoom_grid;
+        self.zoom_image = grid_step.zoom_image;
+        if let Some(fingerprint) = self.fingerprint.as_mut() {
+            fingerprint.zoom_hash = grid_step.zoom_hash;
+        }
+        self.current_step = Some(step);
+        
+        self.history.get(step)
+    }
 }
 
 pub struct GridGenerator;
@@ -103,13 +229,24 @@ impl GridGenerator {
         (squares, screen_info)
     }
     
-    pub fn generate_zoom_grid(parent_square: &OverviewSquare, padding: u32, subdivision: u32) -> (ZoomArea, Vec<ZoomSquare>) {
+    pub fn generate_zoom_grid(
+        parent_square: &OverviewSquare,
+        padding: u32,
+        subdivision: u32,
+        screen_info: &ScreenInfo,
+    ) -> (ZoomArea, Vec<ZoomSquare>) {
+        // Keep the padded area on screen so it can be captured as-is
+        let x = parent_square.x.saturating_sub(padding);
+        let y = parent_square.y.saturating_sub(padding);
+        let right = (parent_square.x + parent_square.width + padding).min(screen_info.width);
+        let bottom = (parent_square.y + parent_square.height + padding).min(screen_info.height);
+        
         let zoom_area = ZoomArea {
             parent_square: parent_square.id.clone(),
-            x: parent_square.x.saturating_sub(padding),
-            y: parent_square.y.saturating_sub(padding),
-            width: parent_square.width + (padding * 2),
-            height: parent_square.height + (padding * 2),
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
             subdivision,
             padding,
         };
@@ -157,7 +294,108 @@ impl GridGenerator {
         squares.iter().find(|s| s.id == id)
     }
     
+    pub fn find_square_at(squares: &[OverviewSquare], x: u32, y: u32) -> Option<&OverviewSquare> {
+        squares.iter().find(|s| x >= s.x && x < s.x + s.width && y >= s.y && y < s.y + s.height)
+    }
+    
+    pub fn find_zoom_square_at(squares: &[ZoomSquare], x: u32, y: u32) -> Option<&ZoomSquare> {
+        squares.iter().find(|s| x >= s.abs_x && x < s.abs_x + s.width && y >= s.abs_y && y < s.abs_y + s.height)
+    }
+    
     pub fn find_zoom_square_by_id<'a>(squares: &'a [ZoomSquare], id: u32) -> Option<&'a ZoomSquare> {
         squares.iter().find(|s| s.id == id)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn to_screen_maps_a_downscaled_overview() {
+        // A 1920 px wide screen saved at 960 px
+        let image = CapturedImage::new(None, "png", 960, 540, 0, 0, 1920);
+        assert_eq!(image.scale, 0.5);
+        assert_eq!(image.to_screen(0.0, 0.0), (0, 0));
+        assert_eq!(image.to_screen(480.0, 270.0), (960, 540));
+    }
+    
+    #[test]
+    fn to_screen_adds_the_origin_of_a_zoom_area() {
+        // A 200 px wide zoom area at (300, 400) enlarged to 800 px
+        let image = CapturedImage::new(None, "png", 800, 800, 300, 400, 200);
+        assert_eq!(image.scale, 4.0);
+        assert_eq!(image.to_screen(0.0, 0.0), (300, 400));
+        assert_eq!(image.to_screen(402.0, 798.0), (401, 600));
+    }
+    
+    fn overview_session() -> SessionData {
+        let (overview_grid, screen_info) = GridGenerator::generate_overview_grid(800, 600, 100);
+        SessionData {
+            version: SESSION_VERSION,
+            overview_grid,
+            selected_square: None,
+            zoom_area: None,
+            zoom_grid: vec![],
+            screen_info,
+            timestamp: chrono::Utc::now(),
+            overview_image: Some(CapturedImage::new(Some(PathBuf::from("overview.png")), "png", 800, 600, 0, 0, 800)),
+            zoom_image: None,
+            session_id: Some("test".to_string()),
+            fingerprint: None,
+            history: vec![],
+            current_step: None,
+        }
+    }
+    
+    fn zoom_into(session_data: &mut SessionData, square_id: &str) {
+        let square = GridGenerator::find_square_by_id(&session_data.overview_grid, square_id).unwrap().clone();
+        let (zoom_area, zoom_grid) = GridGenerator::generate_zoom_grid(&square, 10, 4, &session_data.screen_info);
+        session_data.selected_square = Some(square.id.clone());
+        session_data.zoom_image = Some(CapturedImage::new(
+            Some(PathBuf::from(format!("zoom_{}.png", square.id))), "png", 480, 480, zoom_area.x, zoom_area.y, zoom_area.width,
+        ));
+        session_data.zoom_area = Some(zoom_area);
+        session_data.zoom_grid = zoom_grid;
+        session_data.record_step(&format!("zoom {}", square.id));
+    }
+    
+    #[test]
+    fn record_step_links_each_step_to_the_previous_one() {
+        let mut session_data = overview_session();
+        session_data.record_step("overview");
+        zoom_into(&mut session_data, "B2");
+        
+        assert_eq!(session_data.current_step, Some(1));
+        assert_eq!(session_data.history.len(), 2);
+        assert_eq!(session_data.history[0].previous, None);
+        assert_eq!(session_data.history[0].screenshot, Some(PathBuf::from("overview.png")));
+        assert_eq!(session_data.history[1].previous, Some(0));
+        assert_eq!(session_data.history[1].command, "zoom B2");
+        assert_eq!(session_data.history[1].screenshot, Some(PathBuf::from("zoom_B2.png")));
+    }
+    
+    #[test]
+    fn restore_step_brings_back_the_grid_without_dropping_history() {
+        let mut session_data = overview_session();
+        session_data.record_step("overview");
+        zoom_into(&mut session_data, "B2");
+        zoom_into(&mut session_data, "C3");
+        
+        let restored = session_data.restore_step(1).unwrap();
+        assert_eq!(restored.command, "zoom B2");
+        assert_eq!(session_data.current_step, Some(1));
+        assert_eq!(session_data.selected_square.as_deref(), Some("B2"));
+        assert_eq!(session_data.zoom_area.as_ref().unwrap().parent_square, "B2");
+        assert_eq!(session_data.history.len(), 3);
+        
+        // A zoom after going back branches from the restored step
+        zoom_into(&mut session_data, "D4");
+        assert_eq!(session_data.history[3].previous, Some(1));
+        
+        assert!(session_data.restore_step(0).is_some());
+        assert_eq!(session_data.selected_square, None);
+        assert!(session_data.zoom_grid.is_empty());
+        assert!(session_data.restore_step(9).is_none());
+    }
+}
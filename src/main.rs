@@ -5,16 +5,27 @@ mod overlay;
 mod screenshot;
 mod automation;
 mod session;
+mod emit;
+mod bundle;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use config::Config;
+use config::LayeredConfig;
+use emit::EmitFormat;
+use session::SessionManager;
+use std::path::PathBuf;
+use screenshot::{parse_age, parse_byte_size, parse_millis, ImageBudget, RetentionPolicy, ScreenshotCapture};
 
 #[derive(Parser)]
 #[command(name = "gui-helper")]
 #[command(about = "GUI automation helper with hierarchical grid overlay for AI agents")]
 #[command(version = "0.1.0")]
 pub struct Cli {
+    #[arg(long, global = true, env = "GUI_HELPER_SESSION", default_value = session::DEFAULT_SESSION,
+          value_parser = SessionManager::validate_session_name, help = "Named session to use")]
+    pub session: String,
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE", help = "Override a config value for this run (repeatable)")]
+    pub set: Vec<String>,
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -30,6 +41,14 @@ pub enum Commands {
         output: Option<String>,
         #[arg(long, help = "Export grid coordinates to JSON")]
         json: bool,
+        #[arg(long, help = "Downscale so the longer image side is at most this many pixels")]
+        max_dimension: Option<u32>,
+        #[arg(long, value_parser = parse_byte_size, help = "Re-encode until the image fits in this size (e.g., 500k)")]
+        max_bytes: Option<u64>,
+        #[arg(long, value_enum, conflicts_with_all = ["output", "json"], help = "Print image and grid to stdout instead of saving files")]
+        emit: Option<EmitFormat>,
+        #[arg(long, value_name = "TIME", value_parser = parse_millis, help = "Wait up to this long (e.g., 2s) for the screen to stop changing before capturing")]
+        settle: Option<u64>,
     },
     Zoom {
         #[arg(long, help = "Required: square ID from overview (e.g., 'B5')")]
@@ -44,22 +63,50 @@ pub enum Commands {
         output: Option<String>,
         #[arg(long, help = "Export zoom grid coordinates")]
         json: bool,
+        #[arg(long, help = "Downscale so the longer image side is at most this many pixels")]
+        max_dimension: Option<u32>,
+        #[arg(long, value_parser = parse_byte_size, help = "Re-encode until the image fits in this size (e.g., 500k)")]
+        max_bytes: Option<u64>,
+        #[arg(long, value_enum, conflicts_with_all = ["output", "json"], help = "Print image and grid to stdout instead of saving files")]
+        emit: Option<EmitFormat>,
     },
     Click {
-        #[arg(long, help = "Required: zoom grid square number")]
-        zoom: u32,
+        #[arg(long, required_unless_present = "text", conflicts_with = "text", help = "Zoom grid square number")]
+        zoom: Option<u32>,
+        #[arg(long, help = "Click this text, found on screen by OCR (needs the 'ocr' feature)")]
+        text: Option<String>,
+        #[arg(long, requires = "text", help = "Only look for the text in a region given as x,y,width,height")]
+        region: Option<String>,
         #[arg(long, help = "Click delay override (ms)")]
         delay: Option<u64>,
         #[arg(long, help = "Perform double-click")]
         double: bool,
+        #[arg(long, help = "Click even if the screen no longer matches the grid")]
+        force: bool,
+        #[arg(long, help = "Compare the area around the target before and after the click")]
+        verify: bool,
+        #[arg(long, help = "Like --verify, but fail if nothing changed")]
+        expect_change: bool,
+        #[arg(long, value_name = "MS", help = "Wait before the verify capture (ms)")]
+        verify_delay: Option<u64>,
+    },
+    Locate {
+        #[arg(long, help = "Pixel position x,y on the last captured image")]
+        from_image_px: String,
+        #[arg(long, help = "Position is on the zoom image instead of the overview image")]
+        zoom: bool,
     },
     Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
         #[arg(long, help = "Show current config")]
         show: bool,
         #[arg(long, help = "Reset to defaults")]
         reset: bool,
     },
     Session {
+        #[command(subcommand)]
+        action: Option<SessionAction>,
         #[arg(long, help = "Clear session data")]
         clear: bool,
     },
@@ -83,6 +130,92 @@ pub enum Commands {
         #[command(subcommand)]
         operation: WindowOperation,
     },
+    Screenshots {
+        #[command(subcommand)]
+        action: ScreenshotsAction,
+    },
+    Screenshot {
+        #[arg(long, conflicts_with_all = ["square", "window"], help = "Capture a region given as x,y,width,height")]
+        region: Option<String>,
+        #[arg(long, conflicts_with = "window", help = "Capture an overview square (e.g., 'B5')")]
+        square: Option<String>,
+        #[arg(long, help = "Capture only this window (name, title or ID)")]
+        window: Option<String>,
+        #[arg(long, help = "Resize factor applied before encoding (e.g., 0.5; at most 8)")]
+        scale: Option<f32>,
+        #[arg(long, help = "Image format: png, jpeg or webp")]
+        format: Option<String>,
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=100), help = "JPEG quality (1-100); PNG and WebP are always lossless")]
+        quality: Option<u8>,
+        #[arg(long, conflicts_with = "stdout", help = "Screenshot filename")]
+        output: Option<String>,
+        #[arg(long, help = "Write the encoded image to stdout instead of a file")]
+        stdout: bool,
+    },
+    Find {
+        #[arg(long, help = "Required: image of what to look for, or the name of a saved template")]
+        template: PathBuf,
+        #[arg(long, default_value_t = 0.9, help = "Minimum match score, 0.0 to 1.0")]
+        threshold: f64,
+        #[arg(long, help = "Only search a region given as x,y,width,height")]
+        region: Option<String>,
+        #[arg(long, help = "Report every match instead of the best one")]
+        all: bool,
+        #[arg(long, help = "Print matches as JSON")]
+        json: bool,
+    },
+    Ocr {
+        #[arg(long, conflicts_with = "square", help = "Only read a region given as x,y,width,height")]
+        region: Option<String>,
+        #[arg(long, help = "Only read an overview square (e.g., 'B5')")]
+        square: Option<String>,
+        #[arg(long, help = "Tesseract language(s), e.g. 'eng+deu' (default: ocr.language)")]
+        lang: Option<String>,
+        #[arg(long, value_name = "N", help = "Drop words below this confidence, 0-100 (default: ocr.min_confidence)")]
+        min_confidence: Option<f32>,
+        #[arg(long, help = "Print words as JSON")]
+        json: bool,
+    },
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    WaitStable {
+        #[arg(long, help = "Only watch a region given as x,y,width,height")]
+        region: Option<String>,
+        #[arg(long, value_name = "TIME", value_parser = parse_millis, help = "Give up after this long, e.g. 10s (default: settle.timeout)")]
+        timeout: Option<u64>,
+        #[arg(long, value_name = "TIME", value_parser = parse_millis, help = "Time between captures (default: settle.interval)")]
+        interval: Option<u64>,
+        #[arg(long, value_name = "N", help = "Consecutive unchanged captures required (default: settle.frames)")]
+        frames: Option<u32>,
+        #[arg(long, help = "Fraction of pixels allowed to change between captures (default: settle.threshold)")]
+        threshold: Option<f64>,
+    },
+    WaitChange {
+        #[arg(long, conflicts_with = "region", help = "Watch an overview square (e.g., 'B5')")]
+        square: Option<String>,
+        #[arg(long, help = "Watch a region given as x,y,width,height")]
+        region: Option<String>,
+        #[arg(long, value_name = "TIME", value_parser = parse_millis, help = "Give up after this long, e.g. 10s (default: wait.timeout)")]
+        timeout: Option<u64>,
+        #[arg(long, value_name = "TIME", value_parser = parse_millis, help = "Time between captures (default: wait.interval)")]
+        interval: Option<u64>,
+        #[arg(long, help = "Fraction of pixels that must change (default: automation.change_threshold)")]
+        threshold: Option<f64>,
+    },
+    WaitPixel {
+        #[arg(long, value_name = "X,Y", help = "Required: screen position to watch")]
+        at: String,
+        #[arg(long, help = "Required: color to wait for (e.g., '#00ff00' or 'green')")]
+        color: String,
+        #[arg(long, default_value_t = 10, help = "Allowed difference per color channel (0-255)")]
+        tolerance: u8,
+        #[arg(long, value_name = "TIME", value_parser = parse_millis, help = "Give up after this long, e.g. 10s (default: wait.timeout)")]
+        timeout: Option<u64>,
+        #[arg(long, value_name = "TIME", value_parser = parse_millis, help = "Time between captures (default: wait.interval)")]
+        interval: Option<u64>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -91,28 +224,209 @@ pub enum WindowOperation {
     Unmaximize,
 }
 
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Show the effective configuration
+    Show {
+        #[arg(long, help = "Show which layer each value came from")]
+        origin: bool,
+    },
+    /// Print one value or section, e.g. zoom_grid.subdivision
+    Get {
+        #[arg(help = "Dotted key")]
+        key: String,
+    },
+    /// Change a value in the user config file
+    Set {
+        #[arg(help = "Dotted key")]
+        key: String,
+        #[arg(help = "New value")]
+        value: String,
+        #[arg(long, help = "Write to the project's .gui-helper.toml instead")]
+        project: bool,
+    },
+    /// Open the user config file in $VISUAL/$EDITOR, keeping it only if it is valid
+    Edit {
+        #[arg(long, help = "Edit the project's .gui-helper.toml instead")]
+        project: bool,
+    },
+    /// Check every config value and report the ones out of range
+    Validate,
+}
+
+#[derive(Subcommand)]
+pub enum SessionAction {
+    /// List all stored sessions
+    List,
+    /// Show a session without expiring it
+    Show {
+        #[arg(help = "Session name")]
+        name: String,
+    },
+    /// Delete a session
+    Delete {
+        #[arg(help = "Session name")]
+        name: String,
+    },
+    /// Return to the grid state before the last zoom
+    Back,
+    /// List the grid states of the session with their screenshots
+    History {
+        #[arg(long, help = "Print as JSON")]
+        json: bool,
+    },
+    /// Bundle the session, its screenshots, config and action log into a .tar.gz
+    Export {
+        #[arg(help = "Bundle to write (e.g., run.tar.gz)")]
+        output: PathBuf,
+    },
+    /// Restore a session from a bundle
+    Import {
+        #[arg(help = "Bundle written by 'session export'")]
+        bundle: PathBuf,
+        #[arg(long = "as", value_parser = SessionManager::validate_session_name, help = "Session name to import as (default: the exported name)")]
+        name: Option<String>,
+        #[arg(long, help = "Replace an existing session with the same name")]
+        force: bool,
+    },
+    /// Print a timeline of a session or bundle
+    Inspect {
+        #[arg(help = "Bundle to inspect instead of the current session")]
+        bundle: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ScreenshotsAction {
+    /// List saved screenshots, newest first
+    List {
+        #[arg(long, help = "Print as JSON")]
+        json: bool,
+    },
+    /// Delete old screenshots (defaults to the [retention] config)
+    Prune {
+        #[arg(long, help = "Keep at most this many screenshots")]
+        keep: Option<usize>,
+        #[arg(long, value_parser = parse_age, help = "Delete screenshots older than this (e.g., 7d, 12h)")]
+        older_than: Option<chrono::Duration>,
+        #[arg(long, value_parser = parse_byte_size, help = "Cap the total size (e.g., 1G, 500M)")]
+        max_size: Option<u64>,
+        #[arg(long, help = "Only show what would be removed")]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TemplateAction {
+    /// Crop a zoom cell, square or region from the last screenshot into the library
+    Save {
+        #[arg(help = "Template name (letters, digits, '-' and '_')")]
+        name: String,
+        #[arg(long, required_unless_present = "region", conflicts_with = "region", help = "Zoom cell (e.g., 'B5.23') or overview square (e.g., 'B5')")]
+        square: Option<String>,
+        #[arg(long, help = "Screen region given as x,y,width,height")]
+        region: Option<String>,
+        #[arg(long, default_value_t = 0, help = "Extra pixels to include on each side")]
+        pad: u32,
+        #[arg(long, help = "Capture the area from the screen instead of the last screenshot")]
+        from_screen: bool,
+        #[arg(long, help = "Replace an existing template with the same name")]
+        force: bool,
+    },
+    /// List saved templates
+    List {
+        #[arg(long, help = "Print as JSON")]
+        json: bool,
+    },
+    /// Delete a saved template
+    Delete {
+        #[arg(help = "Template name")]
+        name: String,
+    },
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let config = Config::load()?;
+    let is_config_command = matches!(cli.command, Commands::Config { .. });
+    let mut layered = match LayeredConfig::load(&cli.set) {
+        Ok(layered) => layered,
+        Err(e) if matches!(cli.command, Commands::Config { action: Some(ConfigAction::Validate), .. }) => return Err(e),
+        // Keep `config` usable so the broken layer can be fixed
+        Err(e) if is_config_command => {
+            eprintln!("⚠ {}", e);
+            LayeredConfig::defaults()?
+        }
+        Err(e) => return Err(e),
+    };
     
-    match cli.command {
-        Commands::Overview { grid_size, duration, output, json } => {
-            commands::overview::execute(&config, grid_size, duration, output, json)
+    // Command options go through the same checks as the config they override
+    apply_flag_overrides(&mut layered, &cli.command)?;
+    if !is_config_command {
+        layered.validate()?;
+    }
+    // Overview rows are labelled per display, so its square size is checked against the screen
+    if matches!(cli.command, Commands::Overview { .. }) {
+        let (screen_width, screen_height) = ScreenshotCapture::get_primary_display_size()?;
+        layered.validate_for_display(screen_width, screen_height)?;
+    }
+    let config = layered.config.clone();
+    
+    // Commands that act on the session's grid go into its action log
+    let log_action = matches!(
+        cli.command,
+        Commands::Overview { .. } | Commands::Zoom { .. } | Commands::Click { .. } | Commands::Locate { .. }
+            | Commands::Session { action: Some(SessionAction::Back), .. }
+    );
+    
+    let result = match cli.command {
+        Commands::Overview { grid_size, duration, output, json, max_dimension, max_bytes, emit, settle } => {
+            let budget = ImageBudget { max_dimension, max_bytes };
+            commands::overview::execute(&config, &cli.session, grid_size, duration, output, json, &budget, emit, settle.is_some())
+        },
+        Commands::Zoom { square, padding, subdivision, duration, output, json, max_dimension, max_bytes, emit } => {
+            let budget = ImageBudget { max_dimension, max_bytes };
+            commands::zoom::execute(&config, &cli.session, &square, padding, subdivision, duration, output, json, &budget, emit)
         },
-        Commands::Zoom { square, padding, subdivision, duration, output, json } => {
-            commands::zoom::execute(&config, &square, padding, subdivision, duration, output, json)
+        Commands::Click { zoom, text, region, delay, double, force, verify, expect_change, .. } => match (zoom, text) {
+            (Some(zoom), _) => {
+                commands::click::execute(&config, &cli.session, zoom, delay, double, force, verify || expect_change, expect_change)
+            },
+            (None, Some(text)) => commands::click::text(
+                &config,
+                &cli.session,
+                &text,
+                region.as_deref(),
+                delay,
+                double,
+                verify || expect_change,
+                expect_change,
+            ),
+            (None, None) => unreachable!("clap requires --zoom or --text"),
         },
-        Commands::Click { zoom, delay, double } => {
-            commands::click::execute(&config, zoom, delay, double)
+        Commands::Locate { from_image_px, zoom } => {
+            commands::locate::execute(&config, &cli.session, &from_image_px, zoom)
         },
-        Commands::Config { show, reset } => {
-            commands::config::execute(&config, show, reset)
+        Commands::Config { action, show, reset } => match action {
+            Some(ConfigAction::Show { origin }) => commands::config::show(&layered, origin),
+            Some(ConfigAction::Get { key }) => commands::config::get(&layered, &key),
+            Some(ConfigAction::Set { key, value, project }) => commands::config::set(&key, &value, project),
+            Some(ConfigAction::Edit { project }) => commands::config::edit(project),
+            Some(ConfigAction::Validate) => commands::config::validate(&layered),
+            None => commands::config::execute(&layered, show, reset),
         },
-        Commands::Session { clear } => {
-            commands::session::execute(clear)
+        Commands::Session { action, clear } => match action {
+            Some(SessionAction::List) => commands::session::list(&config),
+            Some(SessionAction::Show { name }) => commands::session::show(&name),
+            Some(SessionAction::Delete { name }) => commands::session::delete(&name),
+            Some(SessionAction::Back) => commands::session::back(&config, &cli.session),
+            Some(SessionAction::History { json }) => commands::session::history(&config, &cli.session, json),
+            Some(SessionAction::Export { output }) => commands::session::export(&config, &cli.session, &output),
+            Some(SessionAction::Import { bundle, name, force }) => commands::session::import(&bundle, name, force),
+            Some(SessionAction::Inspect { bundle }) => commands::session::inspect(&cli.session, bundle.as_deref()),
+            None => commands::session::execute(&config, &cli.session, clear),
         },
         Commands::Open { latest, path } => {
-            commands::open::execute(latest, path)
+            commands::open::execute(&config, latest, path)
         },
         Commands::Maximize => {
             commands::maximize::execute(&config)
@@ -126,5 +440,112 @@ fn main() -> Result<()> {
         Commands::Window { get, operation } => {
             commands::window::execute(&config, &get, operation)
         },
+        Commands::Screenshots { action } => match action {
+            ScreenshotsAction::List { json } => commands::screenshots::list(&config, json),
+            ScreenshotsAction::Prune { keep, older_than, max_size, dry_run } => {
+                let policy = RetentionPolicy { keep, older_than, max_size };
+                commands::screenshots::prune(&config, policy, dry_run)
+            },
+        },
+        Commands::Screenshot { region, square, window, scale, format, quality, output, stdout } => {
+            commands::screenshot::execute(&config, &cli.session, commands::screenshot::ScreenshotOptions {
+                region,
+                square,
+                window,
+                scale,
+                format,
+                quality,
+                output,
+                stdout,
+            })
+        },
+        Commands::Find { template, threshold, region, all, json } => {
+            commands::find::execute(&config, &cli.session, &template, threshold, region.as_deref(), all, json)
+        },
+        Commands::Ocr { region, square, json, .. } => {
+            commands::ocr::execute(&config, &cli.session, region.as_deref(), square.as_deref(), json)
+        },
+        Commands::Template { action } => match action {
+            TemplateAction::Save { name, square, region, pad, from_screen, force } => commands::template::save(
+                &config,
+                &cli.session,
+                &name,
+                square.as_deref(),
+                region.as_deref(),
+                pad,
+                from_screen,
+                force,
+            ),
+            TemplateAction::List { json } => commands::template::list(json),
+            TemplateAction::Delete { name } => commands::template::delete(&name),
+        },
+        Commands::WaitStable { region, .. } => commands::wait::stable(&config, region.as_deref()),
+        Commands::WaitChange { square, region, .. } => {
+            commands::wait::change(&config, &cli.session, square.as_deref(), region.as_deref())
+        },
+        Commands::WaitPixel { at, color, tolerance, .. } => commands::wait::pixel(&config, &at, &color, tolerance),
+    };
+    
+    if log_action {
+        let error = result.as_ref().err().map(|e| e.to_string());
+        if let Err(e) = SessionManager::log_action(&cli.session, error) {
+            eprintln!("⚠ Failed to record action: {}", e);
+        }
+    }
+    
+    // Timeouts get their own exit code so scripts can tell them from failures
+    if let Err(e) = &result {
+        if let Some(timeout) = e.downcast_ref::<commands::wait::WaitTimeout>() {
+            eprintln!("Error: {}", timeout);
+            std::process::exit(commands::wait::TIMEOUT_EXIT_CODE);
+        }
+    }
+    
+    result
+}
+
+fn apply_flag_overrides(layered: &mut LayeredConfig, command: &Commands) -> Result<()> {
+    let integer = |value: Option<u32>| value.map(|value| toml::Value::Integer(value.into()));
+    let millis = |value: Option<u64>| value.map(|ms| toml::Value::Integer(ms.min(i64::MAX as u64) as i64));
+    
+    match command {
+        Commands::Overview { grid_size, duration, settle, .. } => {
+            layered.override_flag("overview_grid.square_size", "--grid-size", integer(*grid_size))?;
+            layered.override_flag("display.duration", "--duration", integer(*duration))?;
+            layered.override_flag("settle.timeout", "--settle", millis(*settle))?;
+        },
+        Commands::Zoom { padding, subdivision, duration, .. } => {
+            layered.override_flag("zoom_grid.padding", "--padding", integer(*padding))?;
+            layered.override_flag("zoom_grid.subdivision", "--subdivision", integer(*subdivision))?;
+            layered.override_flag("display.duration", "--duration", integer(*duration))?;
+        },
+        Commands::Click { delay, verify_delay, .. } => {
+            layered.override_flag("automation.click_delay", "--delay", millis(*delay))?;
+            layered.override_flag("automation.verify_delay", "--verify-delay", millis(*verify_delay))?;
+        },
+        Commands::WaitStable { timeout, interval, frames, threshold, .. } => {
+            layered.override_flag("settle.timeout", "--timeout", millis(*timeout))?;
+            layered.override_flag("settle.interval", "--interval", millis(*interval))?;
+            layered.override_flag("settle.frames", "--frames", integer(*frames))?;
+            layered.override_flag("settle.threshold", "--threshold", threshold.map(toml::Value::Float))?;
+        },
+        Commands::WaitChange { timeout, interval, threshold, .. } => {
+            layered.override_flag("wait.timeout", "--timeout", millis(*timeout))?;
+            layered.override_flag("wait.interval", "--interval", millis(*interval))?;
+            layered.override_flag("automation.change_threshold", "--threshold", threshold.map(toml::Value::Float))?;
+        },
+        Commands::WaitPixel { timeout, interval, .. } => {
+            layered.override_flag("wait.timeout", "--timeout", millis(*timeout))?;
+            layered.override_flag("wait.interval", "--interval", millis(*interval))?;
+        },
+        Commands::Ocr { lang, min_confidence, .. } => {
+            layered.override_flag("ocr.language", "--lang", lang.clone().map(toml::Value::String))?;
+            layered.override_flag("ocr.min_confidence", "--min-confidence", min_confidence.map(|value| toml::Value::Float(value.into())))?;
+        },
+        Commands::Screenshot { format, .. } => {
+            layered.override_flag("display.output_format", "--format", format.clone().map(toml::Value::String))?;
+        },
+        _ => {}
     }
+    Ok(())
 }
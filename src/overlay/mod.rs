@@ -1,3 +1,8 @@
+pub mod font;
+pub mod renderer;
 pub mod simple;
+pub mod style;
 
-pub use simple::SimpleOverlay;
\ No newline at end of file
+pub use renderer::{GridRenderer, Placement};
+pub use simple::SimpleOverlay;
+pub use style::{Color, GridStyle};
\ No newline at end of file
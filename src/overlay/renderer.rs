@@ -1,226 +1,579 @@
-use crate::grid::{OverviewSquare, ZoomSquare, ZoomArea};
-use crate::config::{OverviewGridConfig, ZoomGridConfig};
+use anyhow::Result;
+use crate::config::{ContrastMode, LineStyle, OverviewGridConfig, ZoomGridConfig};
+use crate::grid::{OverviewSquare, ZoomArea, ZoomSquare};
+use crate::overlay::font;
+use crate::overlay::style::{Color, GridStyle};
+use image::{Rgba, RgbaImage};
+use std::collections::HashSet;
+use std::ops::Range;
 
+/// WCAG AA minimum for normal text. Label colors that fall below it against
+/// the captured image are replaced with black or white.
+pub const MIN_LABEL_CONTRAST: f64 = 4.5;
+
+/// Draws grids into captured screenshots. Everything is composited source-over,
+/// so `opacity` lets the screen show through instead of punching holes in it.
 pub struct GridRenderer;
 
-#[derive(Debug, Clone)]
-pub struct Color {
-    pub r: u8,
-    pub g: u8,
-    pub b: u8,
-    pub a: u8,
+/// Where a capture sits on screen, for mapping screen coordinates to image pixels.
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    pub origin_x: u32, // Screen x of the image's top-left corner
+    pub origin_y: u32, // Screen y of the image's top-left corner
+    pub scale: f64,    // Image pixels per screen pixel
+}
+
+// A rectangle in image pixels. Edges can be fractional when the capture is
+// scaled; they are snapped to whole pixels unless anti-aliasing is on.
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
 }
 
-impl Color {
-    pub fn from_name(name: &str, opacity: f32) -> Self {
-        let alpha = (opacity * 255.0) as u8;
-        match name.to_lowercase().as_str() {
-            "red" => Color { r: 255, g: 0, b: 0, a: alpha },
-            "blue" => Color { r: 0, g: 0, b: 255, a: alpha },
-            "green" => Color { r: 0, g: 255, b: 0, a: alpha },
-            "yellow" => Color { r: 255, g: 255, b: 0, a: alpha },
-            "white" => Color { r: 255, g: 255, b: 255, a: alpha },
-            "black" => Color { r: 0, g: 0, b: 0, a: alpha },
-            _ => Color { r: 255, g: 0, b: 0, a: alpha }, // Default to red
+impl Placement {
+    /// `image` shows the screen area starting at (`origin_x`, `origin_y`), `area_width` screen pixels wide.
+    pub fn new(image: &RgbaImage, origin_x: u32, origin_y: u32, area_width: u32) -> Self {
+        Self {
+            origin_x,
+            origin_y,
+            scale: image.width() as f64 / area_width.max(1) as f64,
+        }
+    }
+    
+    fn rect(&self, x: u32, y: u32, width: u32, height: u32) -> Rect {
+        Rect {
+            x: (x as f64 - self.origin_x as f64) * self.scale,
+            y: (y as f64 - self.origin_y as f64) * self.scale,
+            width: width as f64 * self.scale,
+            height: height as f64 * self.scale,
         }
     }
 }
 
 impl GridRenderer {
-    pub fn render_overview_grid(
-        frame: &mut [u8],
-        width: u32,
-        height: u32,
+    /// Draw the overview grid and its labels into `image`.
+    pub fn draw_overview_grid(
+        image: &mut RgbaImage,
+        placement: &Placement,
         squares: &[OverviewSquare],
         config: &OverviewGridConfig,
-    ) {
-        // Clear frame with transparent background
-        Self::clear_frame(frame);
-        
-        let color = Color::from_name(&config.color, config.opacity);
+    ) -> Result<()> {
+        let style = GridStyle::overview(config)?;
+        // Adaptive colors are picked from the capture, not from lines already drawn
+        let background = image.clone();
         
-        // Draw grid lines
         for square in squares {
-            Self::draw_rectangle_outline(
-                frame,
-                width,
-                height,
-                square.x,
-                square.y,
-                square.width,
-                square.height,
-                config.thickness,
-                &color,
-            );
-            
-            if config.show_numbers {
-                Self::draw_text_simple(
-                    frame,
-                    width,
-                    height,
-                    &square.id,
-                    square.x + 5,
-                    square.y + 5,
-                    &color,
-                );
+            let rect = placement.rect(square.x, square.y, square.width, square.height);
+            Self::draw_outline(image, &background, rect, config.thickness, &style);
+        }
+        
+        // Labels go on top of every line
+        if config.show_numbers {
+            let scale = font::scale_for(config.font_size);
+            for square in squares {
+                let rect = placement.rect(square.x, square.y, square.width, square.height);
+                Self::draw_label(image, &background, &square.id, rect.x.round() as i64 + 5, rect.y.round() as i64 + 5, scale, &style);
             }
         }
+        
+        Ok(())
     }
     
-    pub fn render_zoom_grid(
-        frame: &mut [u8],
-        width: u32,
-        height: u32,
+    /// Draw the zoom area border, the zoom grid and its labels into `image`.
+    pub fn draw_zoom_grid(
+        image: &mut RgbaImage,
+        placement: &Placement,
         zoom_area: &ZoomArea,
         squares: &[ZoomSquare],
         config: &ZoomGridConfig,
-    ) {
-        // Clear frame with transparent background
-        Self::clear_frame(frame);
-        
-        let color = Color::from_name(&config.color, config.opacity);
-        
-        // Draw zoom area border (thicker)
-        Self::draw_rectangle_outline(
-            frame,
-            width,
-            height,
-            zoom_area.x,
-            zoom_area.y,
-            zoom_area.width,
-            zoom_area.height,
-            config.thickness + 2,
-            &color,
-        );
-        
-        // Draw grid squares
+    ) -> Result<()> {
+        let style = GridStyle::zoom(config)?;
+        let background = image.clone();
+        
+        // Zoom area border (thicker)
+        let area = placement.rect(zoom_area.x, zoom_area.y, zoom_area.width, zoom_area.height);
+        Self::draw_outline(image, &background, area, config.thickness + 2, &style);
+        
         for square in squares {
-            Self::draw_rectangle_outline(
-                frame,
-                width,
-                height,
-                square.abs_x,
-                square.abs_y,
-                square.width,
-                square.height,
-                config.thickness,
-                &color,
-            );
-            
-            if config.show_numbers {
-                Self::draw_text_simple(
-                    frame,
-                    width,
-                    height,
-                    &square.id.to_string(),
-                    square.abs_x + 2,
-                    square.abs_y + 2,
-                    &color,
-                );
+            let rect = placement.rect(square.abs_x, square.abs_y, square.width, square.height);
+            Self::draw_outline(image, &background, rect, config.thickness, &style);
+        }
+        
+        if config.show_numbers {
+            let scale = font::scale_for(config.font_size);
+            for square in squares {
+                let rect = placement.rect(square.abs_x, square.abs_y, square.width, square.height);
+                Self::draw_label(image, &background, &square.id.to_string(), rect.x.round() as i64 + 2, rect.y.round() as i64 + 2, scale, &style);
             }
         }
+        
+        Ok(())
     }
     
-    fn clear_frame(frame: &mut [u8]) {
-        for pixel in frame.chunks_exact_mut(4) {
-            pixel[0] = 0; // R
-            pixel[1] = 0; // G
-            pixel[2] = 0; // B
-            pixel[3] = 0; // A (transparent)
+    // Four edges, each colored on its own so adaptive modes can follow the background
+    fn draw_outline(image: &mut RgbaImage, background: &RgbaImage, rect: Rect, thickness: u32, style: &GridStyle) {
+        let thickness = Self::edge_thickness(rect, thickness);
+        for (edge, horizontal) in Self::edges(rect, thickness) {
+            let color = Self::line_color(background, edge, style);
+            for piece in Self::pattern(edge, horizontal, thickness, style.line_style) {
+                Self::fill_rect(image, piece, color, style.antialias);
+            }
         }
     }
     
-    fn draw_rectangle_outline(
-        frame: &mut [u8],
-        width: u32,
-        height: u32,
+    fn edge_thickness(rect: Rect, thickness: u32) -> f64 {
+        (thickness as f64).min(rect.width / 2.0).min(rect.height / 2.0)
+    }
+    
+    // Top, bottom, left and right edges, flagged when horizontal. The side edges
+    // stop short of the top and bottom ones so corners are blended once.
+    fn edges(rect: Rect, thickness: f64) -> [(Rect, bool); 4] {
+        let inner_height = rect.height - 2.0 * thickness;
+        [
+            (Rect { height: thickness, ..rect }, true),
+            (Rect { y: rect.y + rect.height - thickness, height: thickness, ..rect }, true),
+            (Rect { y: rect.y + thickness, width: thickness, height: inner_height, ..rect }, false),
+            (Rect { x: rect.x + rect.width - thickness, y: rect.y + thickness, width: thickness, height: inner_height }, false),
+        ]
+    }
+    
+    /// Shade a screen rectangle, e.g. the square chosen for a zoom: `color`
+    /// over the inside and an opaque border of the same color.
+    #[allow(clippy::too_many_arguments)]
+    pub fn shade_rect(
+        image: &mut RgbaImage,
+        placement: &Placement,
         x: u32,
         y: u32,
-        rect_width: u32,
-        rect_height: u32,
-        thickness: u32,
-        color: &Color,
-    ) {
-        // Top edge
-        Self::draw_horizontal_line(frame, width, height, x, y, rect_width, thickness, color);
-        // Bottom edge  
-        Self::draw_horizontal_line(frame, width, height, x, y + rect_height - thickness, rect_width, thickness, color);
-        // Left edge
-        Self::draw_vertical_line(frame, width, height, x, y, rect_height, thickness, color);
-        // Right edge
-        Self::draw_vertical_line(frame, width, height, x + rect_width - thickness, y, rect_height, thickness, color);
-    }
-    
-    fn draw_horizontal_line(
-        frame: &mut [u8],
         width: u32,
         height: u32,
-        x: u32,
-        y: u32,
-        length: u32,
+        color: Color,
         thickness: u32,
-        color: &Color,
     ) {
-        for dy in 0..thickness {
-            for dx in 0..length {
-                let px = x + dx;
-                let py = y + dy;
-                if px < width && py < height {
-                    let index = ((py * width + px) * 4) as usize;
-                    if index + 3 < frame.len() {
-                        frame[index] = color.r;
-                        frame[index + 1] = color.g;
-                        frame[index + 2] = color.b;
-                        frame[index + 3] = color.a;
+        let rect = placement.rect(x, y, width, height);
+        Self::fill_rect(image, rect, color, false);
+        
+        let border = Color { a: 255, ..color };
+        for (edge, _) in Self::edges(rect, Self::edge_thickness(rect, thickness)) {
+            Self::fill_rect(image, edge, border, false);
+        }
+    }
+    
+    /// Crosshair on screen point (`x`, `y`): arms across the whole image with a
+    /// gap and a small box at the center, outlined so it shows on any background.
+    pub fn draw_crosshair(image: &mut RgbaImage, placement: &Placement, x: u32, y: u32, color: Color) {
+        let center = placement.rect(x, y, 1, 1);
+        let (cx, cy) = (center.x.floor(), center.y.floor());
+        let (width, height) = (image.width() as f64, image.height() as f64);
+        let gap = 6.0;
+        
+        let arms = [
+            Rect { x: 0.0, y: cy, width: (cx - gap).max(0.0), height: 1.0 },
+            Rect { x: cx + gap + 1.0, y: cy, width: (width - cx - gap - 1.0).max(0.0), height: 1.0 },
+            Rect { x: cx, y: 0.0, width: 1.0, height: (cy - gap).max(0.0) },
+            Rect { x: cx, y: cy + gap + 1.0, width: 1.0, height: (height - cy - gap - 1.0).max(0.0) },
+        ];
+        let center_box = Rect { x: cx - gap, y: cy - gap, width: 2.0 * gap + 1.0, height: 2.0 * gap + 1.0 };
+        
+        // Halo first, one pixel around every stroke
+        let halo = Color { a: 200, ..color.best_contrast() };
+        let grow = |rect: Rect| Rect { x: rect.x - 1.0, y: rect.y - 1.0, width: rect.width + 2.0, height: rect.height + 2.0 };
+        for arm in arms {
+            Self::fill_rect(image, grow(arm), halo, false);
+        }
+        for (edge, _) in Self::edges(grow(center_box), 3.0) {
+            Self::fill_rect(image, edge, halo, false);
+        }
+        
+        for arm in arms {
+            Self::fill_rect(image, arm, color, false);
+        }
+        for (edge, _) in Self::edges(center_box, 1.0) {
+            Self::fill_rect(image, edge, color, false);
+        }
+    }
+    
+    /// Lines of white text on a dark box in the image's top-left corner.
+    pub fn draw_caption(image: &mut RgbaImage, lines: &[String], scale: u32) {
+        let style = GridStyle {
+            line: Color::rgb(255, 255, 255),
+            text: Color::rgb(255, 255, 255),
+            label_background: Color { r: 0, g: 0, b: 0, a: 0xb3 },
+            text_outline: Color::TRANSPARENT,
+            contrast: ContrastMode::Fixed,
+            line_style: LineStyle::Solid,
+            antialias: false,
+        };
+        let background = image.clone();
+        let line_height = (font::GLYPH_HEIGHT * scale + 6) as i64;
+        for (index, line) in lines.iter().enumerate() {
+            Self::draw_label(image, &background, line, 4, 4 + index as i64 * line_height, scale, &style);
+        }
+    }
+    
+    fn line_color(background: &RgbaImage, segment: Rect, style: &GridStyle) -> Color {
+        let adapted = match style.contrast {
+            ContrastMode::Fixed => return style.line,
+            ContrastMode::Auto => Self::average_color(background, segment).best_contrast(),
+            ContrastMode::Invert => Self::average_color(background, segment).inverted(),
+        };
+        Color { a: style.line.a, ..adapted }
+    }
+    
+    // Split a line into the pieces its style draws: dashes 4x the thickness with
+    // 2x gaps, or square dots with 1x gaps, measured from the line's start.
+    fn pattern(line: Rect, horizontal: bool, thickness: f64, line_style: LineStyle) -> Vec<Rect> {
+        let (on, off) = match line_style {
+            LineStyle::Solid => return vec![line],
+            LineStyle::Dashed => (thickness * 4.0, thickness * 2.0),
+            LineStyle::Dotted => (thickness, thickness),
+        };
+        let (on, off) = (on.max(1.0), off.max(1.0));
+        let length = if horizontal { line.width } else { line.height };
+        
+        let mut pieces = Vec::new();
+        let mut start = 0.0;
+        while start < length {
+            let piece_length = on.min(length - start);
+            pieces.push(if horizontal {
+                Rect { x: line.x + start, width: piece_length, ..line }
+            } else {
+                Rect { y: line.y + start, height: piece_length, ..line }
+            });
+            start += on + off;
+        }
+        pieces
+    }
+    
+    // Label box, text outline and text, each skipped when transparent
+    fn draw_label(
+        image: &mut RgbaImage,
+        background: &RgbaImage,
+        text: &str,
+        x: i64,
+        y: i64,
+        scale: u32,
+        style: &GridStyle,
+    ) {
+        let (text_width, text_height) = font::text_size(text, scale);
+        let label_box = Rect {
+            x: (x - 2) as f64,
+            y: (y - 2) as f64,
+            width: (text_width + 4) as f64,
+            height: (text_height + 4) as f64,
+        };
+        
+        // What the text will be read against: the label box over the captured pixels
+        let backdrop = style.label_background.over(Self::average_color(background, label_box));
+        let (text_color, outline_color) = Self::readable_colors(style, backdrop);
+        
+        if !style.label_background.is_transparent() {
+            Self::fill_rect(image, label_box, style.label_background, false);
+        }
+        
+        let mut text_pixels = HashSet::new();
+        font::for_each_pixel(text, x, y, scale, |px, py| {
+            text_pixels.insert((px, py));
+        });
+        
+        // One ring around the glyphs, blended once per pixel
+        if !outline_color.is_transparent() {
+            let mut outline_pixels = HashSet::new();
+            for (px, py) in &text_pixels {
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        let neighbour = (px + dx, py + dy);
+                        if !text_pixels.contains(&neighbour) {
+                            outline_pixels.insert(neighbour);
+                        }
                     }
                 }
             }
+            for (px, py) in outline_pixels {
+                Self::blend_pixel(image, px, py, outline_color, 1.0);
+            }
+        }
+        
+        for (px, py) in text_pixels {
+            Self::blend_pixel(image, px, py, text_color, 1.0);
         }
     }
     
-    fn draw_vertical_line(
-        frame: &mut [u8],
-        width: u32,
-        height: u32,
-        x: u32,
-        y: u32,
-        length: u32,
-        thickness: u32,
-        color: &Color,
-    ) {
-        for dx in 0..thickness {
-            for dy in 0..length {
-                let px = x + dx;
-                let py = y + dy;
-                if px < width && py < height {
-                    let index = ((py * width + px) * 4) as usize;
-                    if index + 3 < frame.len() {
-                        frame[index] = color.r;
-                        frame[index + 1] = color.g;
-                        frame[index + 2] = color.b;
-                        frame[index + 3] = color.a;
-                    }
+    // Keep the style's text colors unless they fall below WCAG AA against `backdrop`
+    fn readable_colors(style: &GridStyle, backdrop: Color) -> (Color, Color) {
+        if style.text.over(backdrop).contrast_ratio(&backdrop) >= MIN_LABEL_CONTRAST {
+            return (style.text, style.text_outline);
+        }
+        
+        let text = backdrop.best_contrast();
+        let outline = if style.text_outline.is_transparent() {
+            Color::TRANSPARENT
+        } else {
+            text.inverted()
+        };
+        (text, outline)
+    }
+    
+    // Mean color of the pixels touched by `rect` inside the image
+    fn average_color(image: &RgbaImage, rect: Rect) -> Color {
+        let (columns, rows) = Self::clip(image, rect);
+        let (mut r, mut g, mut b, mut count) = (0u64, 0u64, 0u64, 0u64);
+        for y in rows {
+            for x in columns.clone() {
+                let pixel = image.get_pixel(x, y);
+                r += pixel[0] as u64;
+                g += pixel[1] as u64;
+                b += pixel[2] as u64;
+                count += 1;
+            }
+        }
+        
+        if count == 0 {
+            return Color::rgb(0, 0, 0);
+        }
+        Color::rgb((r / count) as u8, (g / count) as u8, (b / count) as u8)
+    }
+    
+    // Without anti-aliasing the edges snap to whole pixels (at least one pixel
+    // wide); with it, partly covered pixels are blended by their coverage.
+    fn fill_rect(image: &mut RgbaImage, rect: Rect, color: Color, antialias: bool) {
+        if !antialias {
+            let left = rect.x.round();
+            let top = rect.y.round();
+            let snapped = Rect {
+                x: left,
+                y: top,
+                width: ((rect.x + rect.width).round() - left).max(1.0),
+                height: ((rect.y + rect.height).round() - top).max(1.0),
+            };
+            let (columns, rows) = Self::clip(image, snapped);
+            for y in rows {
+                for x in columns.clone() {
+                    Self::blend_pixel(image, x as i64, y as i64, color, 1.0);
+                }
+            }
+            return;
+        }
+        
+        let (columns, rows) = Self::clip(image, rect);
+        for y in rows {
+            let coverage_y = Self::overlap(y as f64, rect.y, rect.height);
+            for x in columns.clone() {
+                let coverage = Self::overlap(x as f64, rect.x, rect.width) * coverage_y;
+                if coverage > 0.0 {
+                    Self::blend_pixel(image, x as i64, y as i64, color, coverage);
                 }
             }
         }
     }
     
-    // Simple text rendering (just draw the text as basic pixels)
-    // For a production system, you'd want to use a proper font rendering library
-    fn draw_text_simple(
-        frame: &mut [u8],
-        width: u32,
-        height: u32,
-        text: &str,
-        x: u32,
-        y: u32,
-        color: &Color,
-    ) {
-        // For now, just draw a small rectangle as placeholder for text
-        // In a real implementation, you'd render actual font glyphs
-        let text_width = text.len() as u32 * 6; // Rough approximation
-        let text_height = 8;
+    // How much of the pixel starting at `pixel` lies within [start, start + length)
+    fn overlap(pixel: f64, start: f64, length: f64) -> f64 {
+        ((pixel + 1.0).min(start + length) - pixel.max(start)).clamp(0.0, 1.0)
+    }
+    
+    // Source-over: `color` at its alpha times `coverage` on top of the pixel
+    fn blend_pixel(image: &mut RgbaImage, x: i64, y: i64, color: Color, coverage: f64) {
+        if x < 0 || y < 0 || x as u32 >= image.width() || y as u32 >= image.height() {
+            return;
+        }
+        
+        let pixel = image.get_pixel_mut(x as u32, y as u32);
+        let source_alpha = color.a as f64 / 255.0 * coverage;
+        let dest_alpha = pixel[3] as f64 / 255.0;
+        let out_alpha = source_alpha + dest_alpha * (1.0 - source_alpha);
+        if out_alpha <= 0.0 {
+            return;
+        }
         
-        Self::draw_rectangle_outline(frame, width, height, x, y, text_width, text_height, 1, color);
+        let mix = |source: u8, dest: u8| {
+            let value = (source as f64 * source_alpha + dest as f64 * dest_alpha * (1.0 - source_alpha)) / out_alpha;
+            value.round().clamp(0.0, 255.0) as u8
+        };
+        *pixel = Rgba([
+            mix(color.r, pixel[0]),
+            mix(color.g, pixel[1]),
+            mix(color.b, pixel[2]),
+            (out_alpha * 255.0).round() as u8,
+        ]);
+    }
+    
+    // Column and row ranges of the pixels `rect` touches inside the image
+    fn clip(image: &RgbaImage, rect: Rect) -> (Range<u32>, Range<u32>) {
+        let clamp = |value: f64, max: u32| value.clamp(0.0, max as f64) as u32;
+        let left = clamp(rect.x.floor(), image.width());
+        let top = clamp(rect.y.floor(), image.height());
+        let right = clamp((rect.x + rect.width).ceil(), image.width());
+        let bottom = clamp((rect.y + rect.height).ceil(), image.height());
+        (left..right.max(left), top..bottom.max(top))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::GridGenerator;
+    use crate::config::GridTheme;
+    use std::path::PathBuf;
+    
+    // A screen-like backdrop: a dark-to-light ramp with a white and a black band,
+    // so fixed, adaptive and readable-label colors all show up in the output
+    fn backdrop(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            let level = (x * 255 / width.max(1)) as u8;
+            match y * 4 / height {
+                1 => Rgba([255, 255, 255, 255]),
+                2 => Rgba([0, 0, 0, 255]),
+                _ => Rgba([level, level / 2, 255 - level, 255]),
+            }
+        })
+    }
+    
+    // Compare with tests/golden/<name>.png; set UPDATE_GOLDEN=1 to rewrite it
+    // after an intended change to the rendering
+    fn assert_golden(name: &str, image: &RgbaImage) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.png", name));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            image.save(&path).unwrap();
+            return;
+        }
+        
+        let expected = image::open(&path)
+            .unwrap_or_else(|e| panic!("Missing golden image {:?} ({}); run with UPDATE_GOLDEN=1", path, e))
+            .to_rgba8();
+        assert_eq!(expected.dimensions(), image.dimensions(), "{} has the wrong size", name);
+        
+        if let Some((x, y, pixel)) = expected.enumerate_pixels().find(|(x, y, pixel)| image.get_pixel(*x, *y) != *pixel) {
+            let actual_path = std::env::temp_dir().join(format!("{}.actual.png", name));
+            image.save(&actual_path).unwrap();
+            panic!(
+                "{} differs from {:?} at ({}, {}): expected {:?}, rendered {:?} (saved as {:?})",
+                name, path, x, y, pixel, image.get_pixel(x, y), actual_path
+            );
+        }
+    }
+    
+    #[test]
+    fn golden_overview_grid() {
+        let (squares, _) = GridGenerator::generate_overview_grid(320, 240, 80);
+        let mut image = backdrop(320, 240);
+        let placement = Placement::new(&image, 0, 0, 320);
+        let config = OverviewGridConfig::default();
+        
+        GridRenderer::draw_overview_grid(&mut image, &placement, &squares, &config).unwrap();
+        assert_golden("overview_default", &image);
+    }
+    
+    #[test]
+    fn golden_overview_grid_scaled_down() {
+        // A 640x480 screen saved at half size, as `max_dimension` does
+        let (squares, _) = GridGenerator::generate_overview_grid(640, 480, 160);
+        let mut image = backdrop(320, 240);
+        let placement = Placement::new(&image, 0, 0, 640);
+        let config = OverviewGridConfig { font_size: 12, ..OverviewGridConfig::default() };
+        
+        GridRenderer::draw_overview_grid(&mut image, &placement, &squares, &config).unwrap();
+        assert_golden("overview_scaled", &image);
+    }
+    
+    #[test]
+    fn golden_zoom_grid_with_labels() {
+        let (overview, screen_info) = GridGenerator::generate_overview_grid(400, 400, 100);
+        let square = GridGenerator::find_square_by_id(&overview, "B2").unwrap();
+        let (zoom_area, squares) = GridGenerator::generate_zoom_grid(square, 10, 4, &screen_info);
+        
+        // The zoom area enlarged 2x, with boxed and outlined labels
+        let mut image = backdrop(zoom_area.width * 2, zoom_area.height * 2);
+        let placement = Placement::new(&image, zoom_area.x, zoom_area.y, zoom_area.width);
+        let config = ZoomGridConfig {
+            label_background: "rgba(255, 255, 255, 0.7)".to_string(),
+            text_outline: "white".to_string(),
+            font_size: 16,
+            ..ZoomGridConfig::default()
+        };
+        
+        GridRenderer::draw_zoom_grid(&mut image, &placement, &zoom_area, &squares, &config).unwrap();
+        assert_golden("zoom_labels", &image);
+    }
+    
+    #[test]
+    fn golden_line_styles() {
+        let (squares, _) = GridGenerator::generate_overview_grid(300, 200, 100);
+        let styles = [
+            ("dashed_antialiased", LineStyle::Dashed, true, 1.5),
+            ("dotted", LineStyle::Dotted, false, 1.0),
+        ];
+        
+        for (name, line_style, antialias, scale) in styles {
+            let mut image = backdrop((300.0 * scale) as u32, (200.0 * scale) as u32);
+            let placement = Placement::new(&image, 0, 0, 300);
+            let config = OverviewGridConfig { line_style, antialias, show_numbers: false, ..OverviewGridConfig::default() };
+            
+            GridRenderer::draw_overview_grid(&mut image, &placement, &squares, &config).unwrap();
+            assert_golden(&format!("overview_{}", name), &image);
+        }
+    }
+    
+    #[test]
+    fn golden_themes_and_contrast_modes() {
+        let (squares, _) = GridGenerator::generate_overview_grid(320, 240, 80);
+        let variants = [
+            ("high_contrast", GridTheme::HighContrast, ContrastMode::Fixed),
+            ("colorblind_safe", GridTheme::ColorblindSafe, ContrastMode::Fixed),
+            ("auto_contrast", GridTheme::Custom, ContrastMode::Auto),
+            ("invert_contrast", GridTheme::Custom, ContrastMode::Invert),
+        ];
+        
+        for (name, theme, contrast) in variants {
+            let mut image = backdrop(320, 240);
+            let placement = Placement::new(&image, 0, 0, 320);
+            let config = OverviewGridConfig { theme, contrast, ..OverviewGridConfig::default() };
+            
+            GridRenderer::draw_overview_grid(&mut image, &placement, &squares, &config).unwrap();
+            assert_golden(&format!("overview_{}", name), &image);
+        }
+    }
+    
+    #[test]
+    fn labels_fall_back_to_black_or_white_below_aa() {
+        let style = GridStyle::overview(&OverviewGridConfig { color: "yellow".to_string(), opacity: 1.0, ..OverviewGridConfig::default() }).unwrap();
+        
+        // Yellow reads fine on black but not on white
+        assert_eq!(GridRenderer::readable_colors(&style, Color::rgb(0, 0, 0)).0, style.text);
+        let (text, outline) = GridRenderer::readable_colors(&style, Color::rgb(255, 255, 255));
+        assert_eq!(text, Color::rgb(0, 0, 0));
+        assert_eq!(outline, Color::TRANSPARENT);
+    }
+    
+    #[test]
+    fn line_patterns_repeat_from_the_start_of_the_line() {
+        let line = Rect { x: 10.0, y: 0.0, width: 30.0, height: 2.0 };
+        assert_eq!(GridRenderer::pattern(line, true, 2.0, LineStyle::Solid).len(), 1);
+        
+        // Dashes of 8 with gaps of 4: 10..18, 22..30, 34..40
+        let dashes = GridRenderer::pattern(line, true, 2.0, LineStyle::Dashed);
+        let spans: Vec<(f64, f64)> = dashes.iter().map(|dash| (dash.x, dash.width)).collect();
+        assert_eq!(spans, vec![(10.0, 8.0), (22.0, 8.0), (34.0, 6.0)]);
+        
+        let dots = GridRenderer::pattern(Rect { x: 0.0, y: 5.0, width: 1.0, height: 10.0 }, false, 1.0, LineStyle::Dotted);
+        assert_eq!(dots.iter().map(|dot| dot.y).collect::<Vec<_>>(), vec![5.0, 7.0, 9.0, 11.0, 13.0]);
+    }
+    
+    #[test]
+    fn blending_is_source_over() {
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([0, 0, 255, 255]));
+        GridRenderer::blend_pixel(&mut image, 0, 0, Color { r: 255, g: 0, b: 0, a: 128 }, 1.0);
+        assert_eq!(*image.get_pixel(0, 0), Rgba([128, 0, 127, 255]));
+        
+        // Half coverage of an opaque color is the same as half alpha
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([0, 0, 255, 255]));
+        GridRenderer::blend_pixel(&mut image, 0, 0, Color::rgb(255, 0, 0), 0.5);
+        assert_eq!(*image.get_pixel(0, 0), Rgba([128, 0, 128, 255]));
+        
+        // Off-image pixels are ignored
+        GridRenderer::blend_pixel(&mut image, -1, 3, Color::rgb(255, 0, 0), 1.0);
+    }
+}
@@ -1,29 +1,32 @@
 use anyhow::Result;
+use crate::overlay::GridStyle;
 use std::thread;
 use std::time::Duration;
 
 pub struct SimpleOverlay;
 
 impl SimpleOverlay {
-    pub fn show_overview_grid(duration_secs: u32) -> Result<()> {
-        println!("🎯 Showing overview grid overlay (simulated)...");
-        println!("   Grid is now visible on screen");
-        println!("   Duration: {} seconds", duration_secs);
+    pub fn show_overview_grid(style: &GridStyle, duration_secs: u32) -> Result<()> {
+        eprintln!("🎯 Showing overview grid overlay (simulated)...");
+        eprintln!("   Grid is now visible on screen");
+        eprintln!("   Colors: {}", style);
+        eprintln!("   Duration: {} seconds", duration_secs);
         
         thread::sleep(Duration::from_secs(duration_secs as u64));
         
-        println!("✅ Overview grid overlay finished");
+        eprintln!("✅ Overview grid overlay finished");
         Ok(())
     }
     
-    pub fn show_zoom_grid(zoom_area_info: &str, duration_secs: u32) -> Result<()> {
-        println!("🔍 Showing zoom grid overlay (simulated)...");
-        println!("   {}", zoom_area_info);
-        println!("   Duration: {} seconds", duration_secs);
+    pub fn show_zoom_grid(zoom_area_info: &str, style: &GridStyle, duration_secs: u32) -> Result<()> {
+        eprintln!("🔍 Showing zoom grid overlay (simulated)...");
+        eprintln!("   {}", zoom_area_info);
+        eprintln!("   Colors: {}", style);
+        eprintln!("   Duration: {} seconds", duration_secs);
         
         thread::sleep(Duration::from_secs(duration_secs as u64));
         
-        println!("✅ Zoom grid overlay finished");
+        eprintln!("✅ Zoom grid overlay finished");
         Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,381 @@
+use anyhow::Result;
+use crate::config::{ContrastMode, GridTheme, LineStyle, OverviewGridConfig, ZoomGridConfig};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+/// Resolved colors for one grid level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridStyle {
+    pub line: Color,
+    pub text: Color,
+    pub label_background: Color,
+    pub text_outline: Color,
+    pub contrast: ContrastMode,
+    pub line_style: LineStyle,
+    pub antialias: bool,
+}
+
+impl Color {
+    pub const TRANSPARENT: Color = Color { r: 0, g: 0, b: 0, a: 0 };
+    
+    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 255 }
+    }
+    
+    /// Parse a CSS color: a named color, `#rgb`, `#rrggbb`, `#rrggbbaa`,
+    /// `rgb(r, g, b)` or `rgba(r, g, b, a)` with `a` from 0.0 to 1.0.
+    /// `transparent` and `none` disable the element.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim().to_lowercase();
+        let invalid = || anyhow::anyhow!(
+            "Invalid color '{}'. Expected a CSS color name, #rrggbb, #rrggbbaa or rgb(r, g, b)",
+            spec
+        );
+        
+        if spec == "transparent" || spec == "none" {
+            return Ok(Color::TRANSPARENT);
+        }
+        
+        if let Some(hex) = spec.strip_prefix('#') {
+            return Self::parse_hex(hex).ok_or_else(invalid);
+        }
+        
+        if let Some(arguments) = spec.strip_prefix("rgba(").or_else(|| spec.strip_prefix("rgb(")) {
+            return arguments.strip_suffix(')')
+                .and_then(Self::parse_rgb_arguments)
+                .ok_or_else(invalid);
+        }
+        
+        CSS_COLORS.iter()
+            .find(|(name, _)| *name == spec)
+            .map(|(_, rgb)| Color::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, *rgb as u8))
+            .ok_or_else(invalid)
+    }
+    
+    /// Scale the alpha channel, e.g. by the grid's `opacity`.
+    pub fn with_opacity(self, opacity: f32) -> Self {
+        Color { a: (self.a as f32 * opacity.clamp(0.0, 1.0)).round() as u8, ..self }
+    }
+    
+    pub fn is_transparent(&self) -> bool {
+        self.a == 0
+    }
+    
+    /// `self` drawn source-over on an opaque `below`; the result is opaque.
+    pub fn over(&self, below: Color) -> Color {
+        let alpha = self.a as f32 / 255.0;
+        let mix = |top: u8, bottom: u8| (top as f32 * alpha + bottom as f32 * (1.0 - alpha)).round() as u8;
+        Color::rgb(mix(self.r, below.r), mix(self.g, below.g), mix(self.b, below.b))
+    }
+    
+    pub fn inverted(&self) -> Color {
+        Color { r: 255 - self.r, g: 255 - self.g, b: 255 - self.b, a: self.a }
+    }
+    
+    /// Relative luminance as defined by WCAG 2.x, from 0.0 (black) to 1.0 (white).
+    pub fn luminance(&self) -> f64 {
+        let linear = |channel: u8| {
+            let value = channel as f64 / 255.0;
+            if value <= 0.03928 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+    
+    /// WCAG contrast ratio between two opaque colors, from 1.0 to 21.0.
+    pub fn contrast_ratio(&self, other: &Color) -> f64 {
+        let (lighter, darker) = {
+            let (a, b) = (self.luminance(), other.luminance());
+            if a > b { (a, b) } else { (b, a) }
+        };
+        (lighter + 0.05) / (darker + 0.05)
+    }
+    
+    /// Black or white, whichever contrasts more with `self`. Always at least 4.58:1.
+    pub fn best_contrast(&self) -> Color {
+        let black = Color::rgb(0, 0, 0);
+        let white = Color::rgb(255, 255, 255);
+        if self.contrast_ratio(&black) >= self.contrast_ratio(&white) {
+            black
+        } else {
+            white
+        }
+    }
+    
+    fn parse_hex(hex: &str) -> Option<Self> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        
+        let channel = |index: usize| u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).ok();
+        match hex.len() {
+            3 => {
+                let short = |index: usize| u8::from_str_radix(&hex[index..index + 1], 16).ok().map(|value| value * 17);
+                Some(Color::rgb(short(0)?, short(1)?, short(2)?))
+            }
+            6 => Some(Color::rgb(channel(0)?, channel(1)?, channel(2)?)),
+            8 => Some(Color { r: channel(0)?, g: channel(1)?, b: channel(2)?, a: channel(3)? }),
+            _ => None,
+        }
+    }
+    
+    // "255, 0, 0", "255 0 0" or "255, 0, 0, 0.5"
+    fn parse_rgb_arguments(arguments: &str) -> Option<Self> {
+        let parts: Vec<&str> = arguments
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .collect();
+        
+        let channel = |part: &str| part.parse::<u8>().ok();
+        match parts.as_slice() {
+            [r, g, b] => Some(Color::rgb(channel(r)?, channel(g)?, channel(b)?)),
+            [r, g, b, a] => {
+                let alpha = a.parse::<f32>().ok().filter(|alpha| (0.0..=1.0).contains(alpha))?;
+                Some(Color { r: channel(r)?, g: channel(g)?, b: channel(b)?, a: (alpha * 255.0).round() as u8 })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.a == 255 {
+            write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            write!(f, "#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+        }
+    }
+}
+
+impl GridStyle {
+    pub fn overview(config: &OverviewGridConfig) -> Result<Self> {
+        let style = Self::resolve(config.theme, &config.color, &config.label_background, &config.text_outline, config.opacity, config.contrast)?;
+        Ok(GridStyle { line_style: config.line_style, antialias: config.antialias, ..style })
+    }
+    
+    pub fn zoom(config: &ZoomGridConfig) -> Result<Self> {
+        let style = Self::resolve(config.theme, &config.color, &config.label_background, &config.text_outline, config.opacity, config.contrast)?;
+        Ok(GridStyle { line_style: config.line_style, antialias: config.antialias, ..style })
+    }
+    
+    /// A theme replaces the configured colors; `custom` uses them as given.
+    /// Label text is drawn in the line color unless the theme says otherwise.
+    fn resolve(
+        theme: GridTheme,
+        color: &str,
+        label_background: &str,
+        text_outline: &str,
+        opacity: f32,
+        contrast: ContrastMode,
+    ) -> Result<Self> {
+        let (line, text, label_background, text_outline) = match theme {
+            GridTheme::Custom => {
+                let line = Color::parse(color)?;
+                (line, line, Color::parse(label_background)?, Color::parse(text_outline)?)
+            }
+            // Yellow on black, the usual high-visibility pairing
+            GridTheme::HighContrast => (
+                Color::rgb(0xff, 0xff, 0x00),
+                Color::rgb(0xff, 0xff, 0xff),
+                Color::rgb(0x00, 0x00, 0x00),
+                Color::rgb(0x00, 0x00, 0x00),
+            ),
+            // Okabe-Ito orange and blue, distinguishable with all common color vision deficiencies
+            GridTheme::ColorblindSafe => (
+                Color::rgb(0xe6, 0x9f, 0x00),
+                Color::rgb(0xff, 0xff, 0xff),
+                Color::rgb(0x00, 0x72, 0xb2),
+                Color::rgb(0x00, 0x00, 0x00),
+            ),
+            // For dark screens: light lines, dark label boxes
+            GridTheme::Dark => (
+                Color::rgb(0xf5, 0xf5, 0xf5),
+                Color::rgb(0xff, 0xff, 0xff),
+                Color { r: 0x00, g: 0x00, b: 0x00, a: 0xb3 },
+                Color::rgb(0x00, 0x00, 0x00),
+            ),
+            // For light screens: dark lines, light label boxes
+            GridTheme::Light => (
+                Color::rgb(0x20, 0x20, 0x20),
+                Color::rgb(0x00, 0x00, 0x00),
+                Color { r: 0xff, g: 0xff, b: 0xff, a: 0xb3 },
+                Color::rgb(0xff, 0xff, 0xff),
+            ),
+        };
+        
+        Ok(GridStyle {
+            line: line.with_opacity(opacity),
+            text: text.with_opacity(opacity),
+            label_background: label_background.with_opacity(opacity),
+            text_outline: text_outline.with_opacity(opacity),
+            contrast,
+            line_style: LineStyle::Solid,
+            antialias: false,
+        })
+    }
+}
+
+impl fmt::Display for GridStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "lines {}, labels {}", self.line, self.text)?;
+        if !self.label_background.is_transparent() {
+            write!(f, " on {}", self.label_background)?;
+        }
+        if !self.text_outline.is_transparent() {
+            write!(f, " outlined {}", self.text_outline)?;
+        }
+        match self.line_style {
+            LineStyle::Solid => {}
+            LineStyle::Dashed => write!(f, ", dashed")?,
+            LineStyle::Dotted => write!(f, ", dotted")?,
+        }
+        match self.contrast {
+            ContrastMode::Fixed => {}
+            ContrastMode::Auto => write!(f, " (lines black or white by background)")?,
+            ContrastMode::Invert => write!(f, " (lines inverted from background)")?,
+        }
+        Ok(())
+    }
+}
+
+// CSS Color Module Level 4 named colors as 0xRRGGBB
+const CSS_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff), ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff), ("beige", 0xf5f5dc), ("bisque", 0xffe4c4), ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd), ("blue", 0x0000ff), ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887), ("cadetblue", 0x5f9ea0), ("chartreuse", 0x7fff00), ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed), ("cornsilk", 0xfff8dc), ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff), ("darkblue", 0x00008b), ("darkcyan", 0x008b8b), ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9), ("darkgreen", 0x006400), ("darkgrey", 0xa9a9a9), ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b), ("darkolivegreen", 0x556b2f), ("darkorange", 0xff8c00), ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000), ("darksalmon", 0xe9967a), ("darkseagreen", 0x8fbc8f), ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f), ("darkslategrey", 0x2f4f4f), ("darkturquoise", 0x00ced1), ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493), ("deepskyblue", 0x00bfff), ("dimgray", 0x696969), ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff), ("firebrick", 0xb22222), ("floralwhite", 0xfffaf0), ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff), ("gainsboro", 0xdcdcdc), ("ghostwhite", 0xf8f8ff), ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520), ("gray", 0x808080), ("green", 0x008000), ("greenyellow", 0xadff2f),
+    ("grey", 0x808080), ("honeydew", 0xf0fff0), ("hotpink", 0xff69b4), ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082), ("ivory", 0xfffff0), ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5), ("lawngreen", 0x7cfc00), ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080), ("lightcyan", 0xe0ffff), ("lightgoldenrodyellow", 0xfafad2), ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90), ("lightgrey", 0xd3d3d3), ("lightpink", 0xffb6c1), ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa), ("lightskyblue", 0x87cefa), ("lightslategray", 0x778899), ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de), ("lightyellow", 0xffffe0), ("lime", 0x00ff00), ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6), ("magenta", 0xff00ff), ("maroon", 0x800000), ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd), ("mediumorchid", 0xba55d3), ("mediumpurple", 0x9370db), ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee), ("mediumspringgreen", 0x00fa9a), ("mediumturquoise", 0x48d1cc), ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970), ("mintcream", 0xf5fffa), ("mistyrose", 0xffe4e1), ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead), ("navy", 0x000080), ("oldlace", 0xfdf5e6), ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23), ("orange", 0xffa500), ("orangered", 0xff4500), ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa), ("palegreen", 0x98fb98), ("paleturquoise", 0xafeeee), ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5), ("peachpuff", 0xffdab9), ("peru", 0xcd853f), ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd), ("powderblue", 0xb0e0e6), ("purple", 0x800080), ("rebeccapurple", 0x663399),
+    ("red", 0xff0000), ("rosybrown", 0xbc8f8f), ("royalblue", 0x4169e1), ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072), ("sandybrown", 0xf4a460), ("seagreen", 0x2e8b57), ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d), ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb), ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090), ("slategrey", 0x708090), ("snow", 0xfffafa), ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4), ("tan", 0xd2b48c), ("teal", 0x008080), ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347), ("turquoise", 0x40e0d0), ("violet", 0xee82ee), ("wheat", 0xf5deb3),
+    ("white", 0xffffff), ("whitesmoke", 0xf5f5f5), ("yellow", 0xffff00), ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parses_every_color_syntax() {
+        assert_eq!(Color::parse("red").unwrap(), Color::rgb(255, 0, 0));
+        assert_eq!(Color::parse(" RebeccaPurple ").unwrap(), Color::rgb(0x66, 0x33, 0x99));
+        assert_eq!(Color::parse("#0f8").unwrap(), Color::rgb(0x00, 0xff, 0x88));
+        assert_eq!(Color::parse("#1a2b3c").unwrap(), Color::rgb(0x1a, 0x2b, 0x3c));
+        assert_eq!(Color::parse("#1a2b3c80").unwrap(), Color { r: 0x1a, g: 0x2b, b: 0x3c, a: 0x80 });
+        assert_eq!(Color::parse("rgb(1, 2, 3)").unwrap(), Color::rgb(1, 2, 3));
+        assert_eq!(Color::parse("rgb(1 2 3)").unwrap(), Color::rgb(1, 2, 3));
+        assert_eq!(Color::parse("rgba(1, 2, 3, 0.5)").unwrap(), Color { r: 1, g: 2, b: 3, a: 128 });
+        assert_eq!(Color::parse("transparent").unwrap(), Color::TRANSPARENT);
+        assert_eq!(Color::parse("none").unwrap(), Color::TRANSPARENT);
+    }
+    
+    #[test]
+    fn rejects_invalid_colors() {
+        for spec in ["", "reddish", "#12", "#12345", "#ggg", "rgb(1, 2)", "rgb(256, 0, 0)", "rgba(1, 2, 3, 2)", "rgb(1, 2, 3"] {
+            assert!(Color::parse(spec).is_err(), "{:?}", spec);
+        }
+    }
+    
+    #[test]
+    fn displays_as_hex_that_parses_back() {
+        for color in [Color::rgb(0x12, 0xab, 0xef), Color { r: 1, g: 2, b: 3, a: 4 }] {
+            assert_eq!(Color::parse(&color.to_string()).unwrap(), color);
+        }
+        assert_eq!(Color::rgb(255, 0, 0).to_string(), "#ff0000");
+    }
+    
+    #[test]
+    fn opacity_scales_alpha_and_blends_over_the_background() {
+        let red = Color::rgb(255, 0, 0);
+        assert_eq!(red.with_opacity(0.5).a, 128);
+        assert_eq!(red.with_opacity(2.0).a, 255);
+        assert_eq!(red.with_opacity(0.5).over(Color::rgb(0, 0, 255)), Color::rgb(128, 0, 127));
+        assert_eq!(Color::TRANSPARENT.over(Color::rgb(9, 9, 9)), Color::rgb(9, 9, 9));
+        assert_eq!(red.inverted(), Color::rgb(0, 255, 255));
+    }
+    
+    #[test]
+    fn contrast_ratio_follows_wcag() {
+        let black = Color::rgb(0, 0, 0);
+        let white = Color::rgb(255, 255, 255);
+        assert!((white.luminance() - 1.0).abs() < 1e-9);
+        assert_eq!(black.luminance(), 0.0);
+        assert!((black.contrast_ratio(&white) - 21.0).abs() < 1e-9);
+        assert_eq!(white.contrast_ratio(&black), black.contrast_ratio(&white));
+        assert_eq!(white.contrast_ratio(&white), 1.0);
+        
+        // #767676 on white is the usual example just above AA (4.54:1)
+        let gray = Color::rgb(0x76, 0x76, 0x76);
+        assert!((gray.contrast_ratio(&white) - 4.54).abs() < 0.01);
+    }
+    
+    #[test]
+    fn best_contrast_is_always_readable() {
+        assert_eq!(Color::rgb(255, 255, 0).best_contrast(), Color::rgb(0, 0, 0));
+        assert_eq!(Color::rgb(0, 0, 128).best_contrast(), Color::rgb(255, 255, 255));
+        
+        for level in 0..=255u8 {
+            for color in [Color::rgb(level, level, level), Color::rgb(level, 0, 0), Color::rgb(0, level, 0), Color::rgb(0, 0, level)] {
+                assert!(color.best_contrast().contrast_ratio(&color) >= 4.58, "{}", color);
+            }
+        }
+    }
+    
+    #[test]
+    fn themes_replace_the_configured_colors() {
+        let mut config = ZoomGridConfig { color: "red".to_string(), opacity: 1.0, ..ZoomGridConfig::default() };
+        assert_eq!(GridStyle::zoom(&config).unwrap().line, Color::rgb(255, 0, 0));
+        assert_eq!(GridStyle::zoom(&config).unwrap().text, Color::rgb(255, 0, 0));
+        
+        config.theme = GridTheme::HighContrast;
+        let style = GridStyle::zoom(&config).unwrap();
+        assert_eq!(style.line, Color::rgb(0xff, 0xff, 0x00));
+        assert_eq!(style.label_background, Color::rgb(0, 0, 0));
+        
+        config.opacity = 0.5;
+        assert_eq!(GridStyle::zoom(&config).unwrap().line.a, 128);
+        
+        config.theme = GridTheme::Custom;
+        config.color = "nope".to_string();
+        assert!(GridStyle::zoom(&config).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::Result;
+use crate::screenshot::ScreenshotCapture;
+use image::imageops::FilterType;
+use image::RgbaImage;
+
+// Images are never shrunk below this on their longer side while searching
+const MIN_DIMENSION: u32 = 64;
+const MIN_JPEG_QUALITY: u8 = 10;
+const MAX_JPEG_QUALITY: u8 = 95;
+
+/// Size limits for images handed to vision models, which charge by size.
+#[derive(Debug, Clone, Default)]
+pub struct ImageBudget {
+    pub max_dimension: Option<u32>,
+    pub max_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EncodedImage {
+    pub bytes: Vec<u8>,
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    pub quality: Option<u8>,
+}
+
+impl ImageBudget {
+    /// Resize and re-encode `image` until it satisfies the budget.
+    ///
+    /// The requested format is tried first. If it is too large, the image is
+    /// re-encoded as JPEG with the highest quality that fits, and shrunk further
+    /// when even the lowest quality doesn't. The `image` crate only writes
+    /// lossless WebP, so JPEG is the lossy fallback for every format.
+    pub fn encode(&self, image: &RgbaImage, format: &str) -> Result<EncodedImage> {
+        let mut current = match self.max_dimension {
+            Some(max_dimension) => Self::fit_dimension(image, max_dimension),
+            None => image.clone(),
+        };
+        
+        loop {
+            let bytes = ScreenshotCapture::encode_image(&current, format, None)?;
+            if self.fits(bytes.len()) {
+                return Ok(EncodedImage::new(bytes, format, &current, None));
+            }
+            
+            if let Some((bytes, quality)) = self.search_jpeg_quality(&current)? {
+                return Ok(EncodedImage::new(bytes, "jpeg", &current, Some(quality)));
+            }
+            
+            if current.width().max(current.height()) <= MIN_DIMENSION {
+                return Err(anyhow::anyhow!(
+                    "Could not fit image into {} bytes even at {}x{}",
+                    self.max_bytes.unwrap_or_default(), current.width(), current.height()
+                ));
+            }
+            
+            let width = (current.width() * 3 / 4).max(1);
+            let height = (current.height() * 3 / 4).max(1);
+            current = image::imageops::resize(&current, width, height, FilterType::Triangle);
+        }
+    }
+    
+    fn fits(&self, size: usize) -> bool {
+        self.max_bytes.is_none_or(|max_bytes| size as u64 <= max_bytes)
+    }
+    
+    fn fit_dimension(image: &RgbaImage, max_dimension: u32) -> RgbaImage {
+        let longest = image.width().max(image.height());
+        if longest <= max_dimension {
+            return image.clone();
+        }
+        
+        let scale = max_dimension as f64 / longest as f64;
+        let width = ((image.width() as f64 * scale).round() as u32).max(1);
+        let height = ((image.height() as f64 * scale).round() as u32).max(1);
+        image::imageops::resize(image, width, height, FilterType::Lanczos3)
+    }
+    
+    // Binary search for the highest JPEG quality that fits the byte budget
+    fn search_jpeg_quality(&self, image: &RgbaImage) -> Result<Option<(Vec<u8>, u8)>> {
+        let (mut low, mut high) = (MIN_JPEG_QUALITY, MAX_JPEG_QUALITY);
+        let mut best = None;
+        
+        while low <= high {
+            let quality = low + (high - low) / 2;
+            let bytes = ScreenshotCapture::encode_image(image, "jpeg", Some(quality))?;
+            
+            if self.fits(bytes.len()) {
+                best = Some((bytes, quality));
+                low = quality + 1;
+            } else {
+                high = quality - 1;
+            }
+        }
+        
+        Ok(best)
+    }
+}
+
+impl EncodedImage {
+    fn new(bytes: Vec<u8>, format: &str, image: &RgbaImage, quality: Option<u8>) -> Self {
+        Self {
+            bytes,
+            format: format.to_lowercase(),
+            width: image.width(),
+            height: image.height(),
+            quality,
+        }
+    }
+    
+    pub fn describe(&self) -> String {
+        match self.quality {
+            Some(quality) => format!("{}x{} {} at quality {} ({} bytes)", self.width, self.height, self.format, quality, self.bytes.len()),
+            None => format!("{}x{} {} ({} bytes)", self.width, self.height, self.format, self.bytes.len()),
+        }
+    }
+}
+
+/// Parse sizes like `500k`, `2M` or `1G` (binary multiples) into bytes.
+pub fn parse_byte_size(size: &str) -> Result<u64> {
+    let size = size.trim();
+    let split = size.find(|c: char| !c.is_ascii_digit()).unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    
+    let number: u64 = number.parse()
+        .map_err(|_| anyhow::anyhow!("Invalid size '{}'. Expected e.g. 500k, 2M or 1G", size))?;
+    
+    let multiplier = match unit.to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1024,
+        "m" | "mb" => 1024 * 1024,
+        "g" | "gb" => 1024 * 1024 * 1024,
+        _ => return Err(anyhow::anyhow!("Invalid size unit '{}' in '{}'", unit, size)),
+    };
+    
+    number.checked_mul(multiplier)
+        .ok_or_else(|| anyhow::anyhow!("Size '{}' is too large", size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    
+    // Noise compresses badly, so byte budgets actually have to work for it
+    fn noise(width: u32, height: u32) -> RgbaImage {
+        let mut state: u32 = 12345;
+        RgbaImage::from_fn(width, height, |_, _| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let [r, g, b, _] = state.to_le_bytes();
+            Rgba([r, g, b, 255])
+        })
+    }
+    
+    #[test]
+    fn parses_byte_sizes_with_binary_units() {
+        assert_eq!(parse_byte_size("512").unwrap(), 512);
+        assert_eq!(parse_byte_size("10b").unwrap(), 10);
+        assert_eq!(parse_byte_size("500k").unwrap(), 500 * 1024);
+        assert_eq!(parse_byte_size("2MB").unwrap(), 2 * 1024 * 1024);
+        assert_eq!(parse_byte_size(" 1G ").unwrap(), 1024 * 1024 * 1024);
+    }
+    
+    #[test]
+    fn rejects_invalid_byte_sizes() {
+        assert!(parse_byte_size("").is_err());
+        assert!(parse_byte_size("k").is_err());
+        assert!(parse_byte_size("1.5M").is_err());
+        assert!(parse_byte_size("10x").is_err());
+        assert!(parse_byte_size("-1k").is_err());
+    }
+    
+    #[test]
+    fn rejects_byte_sizes_that_overflow() {
+        assert!(parse_byte_size("99999999999999G").is_err());
+        assert!(parse_byte_size("99999999999999999999").is_err());
+    }
+    
+    #[test]
+    fn max_dimension_scales_the_longer_side() {
+        let budget = ImageBudget { max_dimension: Some(100), max_bytes: None };
+        let encoded = budget.encode(&noise(400, 200), "png").unwrap();
+        assert_eq!((encoded.width, encoded.height), (100, 50));
+        assert_eq!(encoded.format, "png");
+        assert_eq!(encoded.quality, None);
+    }
+    
+    #[test]
+    fn small_images_are_not_enlarged() {
+        let budget = ImageBudget { max_dimension: Some(1000), max_bytes: None };
+        let encoded = budget.encode(&noise(40, 30), "png").unwrap();
+        assert_eq!((encoded.width, encoded.height), (40, 30));
+    }
+    
+    #[test]
+    fn falls_back_to_jpeg_to_fit_max_bytes() {
+        let image = noise(200, 200);
+        let budget = ImageBudget { max_dimension: None, max_bytes: Some(20_000) };
+        let encoded = budget.encode(&image, "png").unwrap();
+        
+        assert!(encoded.bytes.len() <= 20_000);
+        assert_eq!(encoded.format, "jpeg");
+        assert!(encoded.quality.is_some());
+    }
+    
+    #[test]
+    fn fails_when_the_budget_cannot_be_met() {
+        let budget = ImageBudget { max_dimension: None, max_bytes: Some(10) };
+        assert!(budget.encode(&noise(100, 100), "png").is_err());
+    }
+}
@@ -1,27 +1,66 @@
 use anyhow::Result;
 use screenshots::Screen;
 use image::{ImageBuffer, RgbaImage};
-use std::path::Path;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
 
 pub struct ScreenshotCapture;
 
 impl ScreenshotCapture {
     pub fn get_primary_display_size() -> Result<(u32, u32)> {
-        let screens = Screen::all()?;
-        if let Some(primary) = screens.first() {
-            Ok((primary.display_info.width, primary.display_info.height))
-        } else {
-            Err(anyhow::anyhow!("No displays found"))
-        }
+        let primary = Self::primary_screen()?;
+        Ok((primary.display_info.width, primary.display_info.height))
     }
     
     pub fn capture_screen() -> Result<RgbaImage> {
-        let screens = Screen::all()?;
-        let primary = screens.first()
-            .ok_or_else(|| anyhow::anyhow!("No displays found"))?;
-            
+        let primary = Self::primary_screen()?;
         let image = primary.capture()?;
+        Self::to_rgba_image(image)
+    }
+    
+    pub fn capture_area(x: u32, y: u32, width: u32, height: u32) -> Result<RgbaImage> {
+        let primary = Self::primary_screen()?;
+        Self::validate_area(primary.display_info.width, primary.display_info.height, x, y, width, height)?;
         
+        // Only the requested pixels are read back from the display server
+        let image = primary.capture_area(x as i32, y as i32, width, height)?;
+        Self::to_rgba_image(image)
+    }
+    
+    pub fn validate_area(
+        screen_width: u32,
+        screen_height: u32,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<()> {
+        if width == 0 || height == 0 {
+            return Err(anyhow::anyhow!("Capture area {}x{} is empty", width, height));
+        }
+        
+        let fits_horizontally = x.checked_add(width).is_some_and(|right| right <= screen_width);
+        let fits_vertically = y.checked_add(height).is_some_and(|bottom| bottom <= screen_height);
+        
+        if !fits_horizontally || !fits_vertically {
+            return Err(anyhow::anyhow!(
+                "Capture area {}x{} at ({}, {}) lies outside the {}x{} display",
+                width, height, x, y, screen_width, screen_height
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    fn primary_screen() -> Result<Screen> {
+        Screen::all()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No displays found"))
+    }
+    
+    fn to_rgba_image(image: screenshots::Image) -> Result<RgbaImage> {
         // Convert from screenshots::Image to image::RgbaImage
         let width = image.width();
         let height = image.height();
@@ -31,53 +70,30 @@ impl ScreenshotCapture {
             .ok_or_else(|| anyhow::anyhow!("Failed to create image buffer"))
     }
     
-    pub fn capture_and_save(output_path: &Path, format: &str) -> Result<(u32, u32)> {
-        let image = Self::capture_screen()?;
-        let (width, height) = (image.width(), image.height());
-        
-        // Ensure parent directory exists
-        if let Some(parent) = output_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
+    /// Encode an image in memory. `quality` (1-100) only affects JPEG; PNG and
+    /// WebP are always lossless.
+    pub fn encode_image(image: &RgbaImage, format: &str, quality: Option<u8>) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
         
         match format.to_lowercase().as_str() {
-            "png" => image.save_with_format(output_path, image::ImageFormat::Png)?,
-            "jpeg" | "jpg" => image.save_with_format(output_path, image::ImageFormat::Jpeg)?,
+            "png" => image.write_with_encoder(PngEncoder::new(&mut bytes))?,
+            // JPEG has no alpha channel
+            "jpeg" | "jpg" => image::DynamicImage::ImageRgba8(image.clone())
+                .to_rgb8()
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, quality.unwrap_or(75)))?,
+            "webp" => image.write_with_encoder(WebPEncoder::new_lossless(&mut bytes))?,
             _ => return Err(anyhow::anyhow!("Unsupported image format: {}", format)),
         }
         
-        Ok((width, height))
-    }
-    
-    pub fn capture_area(x: u32, y: u32, width: u32, height: u32) -> Result<RgbaImage> {
-        let full_image = Self::capture_screen()?;
-        
-        // Crop the image to the specified area
-        let cropped = image::imageops::crop_imm(&full_image, x, y, width, height);
-        Ok(cropped.to_image())
+        Ok(bytes)
     }
     
-    pub fn capture_area_and_save(
-        x: u32, 
-        y: u32, 
-        width: u32, 
-        height: u32, 
-        output_path: &Path, 
-        format: &str
-    ) -> Result<()> {
-        let cropped_image = Self::capture_area(x, y, width, height)?;
-        
-        // Ensure parent directory exists
-        if let Some(parent) = output_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        
+    pub fn mime_type(format: &str) -> Result<&'static str> {
         match format.to_lowercase().as_str() {
-            "png" => cropped_image.save_with_format(output_path, image::ImageFormat::Png)?,
-            "jpeg" | "jpg" => cropped_image.save_with_format(output_path, image::ImageFormat::Jpeg)?,
-            _ => return Err(anyhow::anyhow!("Unsupported image format: {}", format)),
+            "png" => Ok("image/png"),
+            "jpeg" | "jpg" => Ok("image/jpeg"),
+            "webp" => Ok("image/webp"),
+            _ => Err(anyhow::anyhow!("Unsupported image format: {}", format)),
         }
-        
-        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,149 @@
+use anyhow::Result;
+use image::{Rgba, RgbaImage};
+
+/// Largest channel difference still treated as unchanged, so dithering and
+/// compression noise don't count as a reaction.
+pub const NOISE_TOLERANCE: u8 = 24;
+
+/// Pixel difference between two captures of the same screen area.
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    pub changed_pixels: u64,
+    pub total_pixels: u64,
+    pub score: f64,                          // Fraction of changed pixels, 0.0 to 1.0
+    pub bounds: Option<(u32, u32, u32, u32)>, // x, y, width, height around the changed pixels
+}
+
+impl ImageDiff {
+    pub fn compute(before: &RgbaImage, after: &RgbaImage) -> Result<Self> {
+        if before.dimensions() != after.dimensions() {
+            return Err(anyhow::anyhow!(
+                "Can't compare captures of different sizes ({}x{} and {}x{})",
+                before.width(), before.height(), after.width(), after.height()
+            ));
+        }
+        
+        let mut changed_pixels = 0u64;
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+        for (x, y, pixel) in after.enumerate_pixels() {
+            if difference(before.get_pixel(x, y), pixel) > NOISE_TOLERANCE {
+                changed_pixels += 1;
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+            }
+        }
+        
+        let total_pixels = after.width() as u64 * after.height() as u64;
+        Ok(Self {
+            changed_pixels,
+            total_pixels,
+            score: changed_pixels as f64 / total_pixels.max(1) as f64,
+            bounds: (changed_pixels > 0).then(|| (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1)),
+        })
+    }
+    
+    /// Whether the change reaches `threshold` (a fraction of the area).
+    pub fn changed(&self, threshold: f64) -> bool {
+        self.changed_pixels > 0 && self.score >= threshold
+    }
+}
+
+/// The `after` capture, grayed and dimmed, with changed pixels in red: the
+/// stronger the change, the brighter the red.
+pub fn heatmap(before: &RgbaImage, after: &RgbaImage) -> Result<RgbaImage> {
+    if before.dimensions() != after.dimensions() {
+        return Err(anyhow::anyhow!("Can't compare captures of different sizes"));
+    }
+    
+    Ok(RgbaImage::from_fn(after.width(), after.height(), |x, y| {
+        let pixel = after.get_pixel(x, y);
+        let [r, g, b, _] = pixel.0;
+        let gray = ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000 / 3) as u8;
+        
+        let delta = difference(before.get_pixel(x, y), pixel);
+        if delta > NOISE_TOLERANCE {
+            Rgba([128 + delta / 2, gray / 2, gray / 2, 255])
+        } else {
+            Rgba([gray, gray, gray, 255])
+        }
+    }))
+}
+
+// Largest per-channel difference, ignoring alpha (captures are opaque)
+fn difference(a: &Rgba<u8>, b: &Rgba<u8>) -> u8 {
+    (0..3).map(|channel| a.0[channel].abs_diff(b.0[channel])).max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn gray(width: u32, height: u32, level: u8) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba([level, level, level, 255]))
+    }
+    
+    #[test]
+    fn identical_captures_have_no_change() {
+        let image = gray(10, 10, 100);
+        let diff = ImageDiff::compute(&image, &image).unwrap();
+        
+        assert_eq!((diff.changed_pixels, diff.total_pixels), (0, 100));
+        assert_eq!(diff.score, 0.0);
+        assert_eq!(diff.bounds, None);
+        assert!(!diff.changed(0.0));
+    }
+    
+    #[test]
+    fn noise_below_the_tolerance_is_ignored() {
+        let before = gray(10, 10, 100);
+        let after = gray(10, 10, 100 + NOISE_TOLERANCE);
+        assert_eq!(ImageDiff::compute(&before, &after).unwrap().changed_pixels, 0);
+        
+        let after = gray(10, 10, 100 + NOISE_TOLERANCE + 1);
+        assert_eq!(ImageDiff::compute(&before, &after).unwrap().changed_pixels, 100);
+    }
+    
+    #[test]
+    fn bounds_and_score_cover_the_changed_pixels() {
+        let before = gray(10, 10, 0);
+        let mut after = before.clone();
+        after.put_pixel(2, 3, Rgba([255, 0, 0, 255]));
+        after.put_pixel(6, 4, Rgba([0, 0, 255, 255]));
+        
+        let diff = ImageDiff::compute(&before, &after).unwrap();
+        assert_eq!(diff.changed_pixels, 2);
+        assert_eq!(diff.score, 0.02);
+        assert_eq!(diff.bounds, Some((2, 3, 5, 2)));
+        assert!(diff.changed(0.02));
+        assert!(!diff.changed(0.03));
+    }
+    
+    #[test]
+    fn alpha_is_ignored() {
+        let before = gray(2, 2, 50);
+        let after = RgbaImage::from_pixel(2, 2, Rgba([50, 50, 50, 0]));
+        assert_eq!(ImageDiff::compute(&before, &after).unwrap().changed_pixels, 0);
+    }
+    
+    #[test]
+    fn captures_of_different_sizes_are_rejected() {
+        assert!(ImageDiff::compute(&gray(2, 2, 0), &gray(2, 3, 0)).is_err());
+        assert!(heatmap(&gray(2, 2, 0), &gray(3, 2, 0)).is_err());
+    }
+    
+    #[test]
+    fn heatmap_marks_changes_in_red_over_dimmed_gray() {
+        let before = gray(2, 1, 0);
+        let mut after = gray(2, 1, 0);
+        after.put_pixel(1, 0, Rgba([200, 200, 200, 255]));
+        
+        let map = heatmap(&before, &after).unwrap();
+        assert_eq!(*map.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+        assert_eq!(*map.get_pixel(1, 0), Rgba([228, 33, 33, 255]));
+        
+        let unchanged = heatmap(&after, &after).unwrap();
+        assert_eq!(*unchanged.get_pixel(1, 0), Rgba([66, 66, 66, 255]));
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use image::imageops::FilterType;
+use image::RgbaImage;
+use screenshots::Screen;
+use serde::{Deserialize, Serialize};
+
+/// Position and size of one monitor, as reported by the display server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplayGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f32,
+}
+
+/// What the screen looked like when a grid was captured. Used to detect
+/// that a grid no longer fits the screen before clicking through it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenFingerprint {
+    pub displays: Vec<DisplayGeometry>,
+    pub overview_hash: u64,       // Perceptual hash of the overview capture
+    pub zoom_hash: Option<u64>,   // Perceptual hash of the zoom capture, once zoomed
+}
+
+impl ScreenFingerprint {
+    pub fn new(overview_image: &RgbaImage) -> Result<Self> {
+        Ok(Self {
+            displays: Self::current_displays()?,
+            overview_hash: perceptual_hash(overview_image),
+            zoom_hash: None,
+        })
+    }
+    
+    pub fn current_displays() -> Result<Vec<DisplayGeometry>> {
+        Ok(Screen::all()?
+            .iter()
+            .map(|screen| DisplayGeometry {
+                x: screen.display_info.x,
+                y: screen.display_info.y,
+                width: screen.display_info.width,
+                height: screen.display_info.height,
+                scale_factor: screen.display_info.scale_factor,
+            })
+            .collect())
+    }
+}
+
+/// Difference hash: the image is shrunk to 9x8 grayscale and each bit records
+/// whether a pixel is brighter than its right neighbour. Small rendering noise
+/// keeps the hash stable, while moved or replaced content flips many bits.
+pub fn perceptual_hash(image: &RgbaImage) -> u64 {
+    let small = image::imageops::resize(image, 9, 8, FilterType::Triangle);
+    let luma = |x: u32, y: u32| {
+        let [r, g, b, _] = small.get_pixel(x, y).0;
+        299 * r as u32 + 587 * g as u32 + 114 * b as u32
+    };
+    
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if luma(x, y) > luma(x + 1, y) {
+                hash |= 1;
+            }
+        }
+    }
+    
+    hash
+}
+
+/// Number of differing bits between two hashes (0-64).
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
@@ -0,0 +1,381 @@
+use anyhow::Result;
+use image::imageops::FilterType;
+use image::RgbaImage;
+
+/// Template sizes tried relative to the saved template, to cover captures
+/// taken at another DPI scale.
+pub const SCALES: &[f64] = &[0.5, 0.67, 0.8, 1.0, 1.25, 1.5, 2.0];
+
+// The coarse pass shrinks images until the template's shorter side is about this
+const COARSE_TEMPLATE_SIDE: u32 = 8;
+
+// How far below the threshold a coarse score may be and still get refined;
+// shrinking blurs detail, so true matches score lower there
+const COARSE_MARGIN: f64 = 0.25;
+
+// Coarse candidates refined per scale when only the best matches are wanted
+const MAX_CANDIDATES: usize = 50;
+
+/// A place in the searched image that looks like the template.
+#[derive(Debug, Clone)]
+pub struct TemplateMatch {
+    pub x: u32,      // Top-left corner, in pixels of the searched image
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub score: f64,  // Normalized cross-correlation, -1.0 to 1.0
+    pub scale: f64,  // Template scale that matched
+}
+
+/// Find `template` in `image` by normalized cross-correlation on grayscale,
+/// at every scale in `SCALES`. Returns non-overlapping matches scoring at
+/// least `threshold`, best first. Without `all`, only the best candidates of
+/// each scale are refined, which is enough to find the top matches faster.
+pub fn find_template(image: &RgbaImage, template: &RgbaImage, threshold: f64, all: bool) -> Result<Vec<TemplateMatch>> {
+    let max_candidates = if all { None } else { Some(MAX_CANDIDATES) };
+    let screen = Gray::from_rgba(image);
+    let screen_integral = Integral::new(&screen);
+    
+    let mut matches = Vec::new();
+    let mut searched = false;
+    for &scale in SCALES {
+        let width = (template.width() as f64 * scale).round() as u32;
+        let height = (template.height() as f64 * scale).round() as u32;
+        if width < 4 || height < 4 || width > image.width() || height > image.height() {
+            continue;
+        }
+        
+        let scaled = if scale == 1.0 {
+            template.clone()
+        } else {
+            image::imageops::resize(template, width, height, FilterType::Triangle)
+        };
+        let prepared = match Prepared::new(Gray::from_rgba(&scaled)) {
+            Some(prepared) => prepared,
+            None => continue,
+        };
+        searched = true;
+        
+        for (x, y, score) in search(&screen, &screen_integral, &prepared, threshold, max_candidates) {
+            matches.push(TemplateMatch { x, y, width, height, score, scale });
+        }
+    }
+    
+    if !searched {
+        return Err(anyhow::anyhow!(
+            "Template {}x{} can't be searched in a {}x{} image (too large, or a single flat color)",
+            template.width(), template.height(), image.width(), image.height()
+        ));
+    }
+    
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(suppress_overlaps(matches, |m| (m.x, m.y, m.width, m.height)))
+}
+
+// Grayscale pixels as floats, row by row
+struct Gray {
+    width: u32,
+    height: u32,
+    data: Vec<f32>,
+}
+
+impl Gray {
+    fn from_rgba(image: &RgbaImage) -> Self {
+        let data = image.pixels()
+            .map(|pixel| {
+                let [r, g, b, _] = pixel.0;
+                0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32
+            })
+            .collect();
+        Self { width: image.width(), height: image.height(), data }
+    }
+    
+    fn at(&self, x: u32, y: u32) -> f32 {
+        self.data[(y * self.width + x) as usize]
+    }
+    
+    // Box-filtered copy, `factor` times smaller in each direction
+    fn shrink(&self, factor: u32) -> Self {
+        if factor <= 1 {
+            return Self { width: self.width, height: self.height, data: self.data.clone() };
+        }
+        let width = (self.width / factor).max(1);
+        let height = (self.height / factor).max(1);
+        let mut data = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = 0.0;
+                for dy in 0..factor {
+                    for dx in 0..factor {
+                        sum += self.at((x * factor + dx).min(self.width - 1), (y * factor + dy).min(self.height - 1));
+                    }
+                }
+                data.push(sum / (factor * factor) as f32);
+            }
+        }
+        Self { width, height, data }
+    }
+}
+
+// Summed-area tables of pixel values and their squares, for the mean and
+// variance of any window in constant time
+struct Integral {
+    stride: usize,
+    sums: Vec<f64>,
+    squares: Vec<f64>,
+}
+
+impl Integral {
+    fn new(gray: &Gray) -> Self {
+        let stride = gray.width as usize + 1;
+        let mut sums = vec![0.0; stride * (gray.height as usize + 1)];
+        let mut squares = sums.clone();
+        for y in 0..gray.height as usize {
+            let (mut row_sum, mut row_squares) = (0.0, 0.0);
+            for x in 0..gray.width as usize {
+                let value = gray.data[y * gray.width as usize + x] as f64;
+                row_sum += value;
+                row_squares += value * value;
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row_sum;
+                squares[(y + 1) * stride + x + 1] = squares[y * stride + x + 1] + row_squares;
+            }
+        }
+        Self { stride, sums, squares }
+    }
+    
+    // Sum and sum of squares over the window
+    fn window(&self, x: u32, y: u32, width: u32, height: u32) -> (f64, f64) {
+        let (x0, y0) = (x as usize, y as usize);
+        let (x1, y1) = (x0 + width as usize, y0 + height as usize);
+        let area = |table: &[f64]| {
+            table[y1 * self.stride + x1] - table[y0 * self.stride + x1] - table[y1 * self.stride + x0] + table[y0 * self.stride + x0]
+        };
+        (area(&self.sums), area(&self.squares))
+    }
+}
+
+// A template with its mean removed, ready for correlation
+struct Prepared {
+    gray: Gray,
+    zero_mean: Vec<f32>,
+    norm: f64,
+}
+
+impl Prepared {
+    // `None` for a flat template, which correlates with nothing
+    fn new(gray: Gray) -> Option<Self> {
+        let mean = gray.data.iter().map(|&value| value as f64).sum::<f64>() / gray.data.len() as f64;
+        let zero_mean: Vec<f32> = gray.data.iter().map(|&value| (value as f64 - mean) as f32).collect();
+        let norm = zero_mean.iter().map(|&value| (value as f64).powi(2)).sum::<f64>().sqrt();
+        (norm > 1e-3).then_some(Self { gray, zero_mean, norm })
+    }
+    
+    fn shrink(&self, factor: u32) -> Option<Self> {
+        Self::new(self.gray.shrink(factor))
+    }
+    
+    // Correlation with the window of `image` at (x, y)
+    fn score(&self, image: &Gray, integral: &Integral, x: u32, y: u32) -> f64 {
+        let (width, height) = (self.gray.width, self.gray.height);
+        let (sum, squares) = integral.window(x, y, width, height);
+        let variance = squares - sum * sum / (width * height) as f64;
+        if variance <= 1e-3 {
+            return 0.0;
+        }
+        
+        // The template sums to zero, so the window's mean drops out
+        let mut dot = 0.0f64;
+        for ty in 0..height {
+            let row = ((y + ty) * image.width + x) as usize;
+            let pixels = &image.data[row..row + width as usize];
+            let weights = &self.zero_mean[(ty * width) as usize..((ty + 1) * width) as usize];
+            dot += pixels.iter().zip(weights).map(|(&pixel, &weight)| pixel * weight).sum::<f32>() as f64;
+        }
+        // Rounding can push a perfect match slightly past 1
+        (dot / (self.norm * variance.sqrt())).clamp(-1.0, 1.0)
+    }
+}
+
+// Top-left corners scoring at least `threshold`. Scores every position of a
+// shrunken copy first, then refines up to `max_candidates` at full resolution.
+fn search(screen: &Gray, integral: &Integral, template: &Prepared, threshold: f64, max_candidates: Option<usize>) -> Vec<(u32, u32, f64)> {
+    let (width, height) = (template.gray.width, template.gray.height);
+    let factor = (width.min(height) / COARSE_TEMPLATE_SIDE).max(1);
+    
+    let coarse_template = if factor > 1 { template.shrink(factor) } else { None };
+    let candidates = match &coarse_template {
+        Some(coarse_template) => {
+            let coarse_screen = screen.shrink(factor);
+            let coarse_integral = Integral::new(&coarse_screen);
+            scan(&coarse_screen, &coarse_integral, coarse_template, (threshold - COARSE_MARGIN).max(0.0), max_candidates)
+        }
+        None => return scan(screen, integral, template, threshold, max_candidates),
+    };
+    
+    let refined = candidates.into_iter()
+        .filter_map(|(coarse_x, coarse_y, _)| {
+            let x_range = (coarse_x * factor).saturating_sub(factor)..=((coarse_x + 1) * factor).min(screen.width - width);
+            let y_range = (coarse_y * factor).saturating_sub(factor)..=((coarse_y + 1) * factor).min(screen.height - height);
+            y_range
+                .flat_map(|y| x_range.clone().map(move |x| (x, y)))
+                .map(|(x, y)| (x, y, template.score(screen, integral, x, y)))
+                .max_by(|a, b| a.2.total_cmp(&b.2))
+        })
+        .filter(|&(_, _, score)| score >= threshold)
+        .collect();
+    
+    suppress_overlaps(refined, |&(x, y, _)| (x, y, width, height))
+}
+
+// Score every position, keeping the best non-overlapping ones above `threshold`
+fn scan(image: &Gray, integral: &Integral, template: &Prepared, threshold: f64, max_candidates: Option<usize>) -> Vec<(u32, u32, f64)> {
+    let (width, height) = (template.gray.width, template.gray.height);
+    if width > image.width || height > image.height {
+        return Vec::new();
+    }
+    
+    let mut hits = Vec::new();
+    for y in 0..=image.height - height {
+        for x in 0..=image.width - width {
+            let score = template.score(image, integral, x, y);
+            if score >= threshold {
+                hits.push((x, y, score));
+            }
+        }
+    }
+    
+    hits.sort_by(|a, b| b.2.total_cmp(&a.2));
+    let mut kept = suppress_overlaps(hits, |&(x, y, _)| (x, y, width, height));
+    if let Some(max_candidates) = max_candidates {
+        kept.truncate(max_candidates);
+    }
+    kept
+}
+
+// Drop every item overlapping a better one (listed earlier) by more than half
+// of the smaller rectangle
+fn suppress_overlaps<T>(sorted: Vec<T>, rect: impl Fn(&T) -> (u32, u32, u32, u32)) -> Vec<T> {
+    let mut kept: Vec<T> = Vec::new();
+    for item in sorted {
+        let (x, y, width, height) = rect(&item);
+        let overlaps = kept.iter().any(|other| {
+            let (ox, oy, other_width, other_height) = rect(other);
+            let overlap_width = (x + width).min(ox + other_width).saturating_sub(x.max(ox));
+            let overlap_height = (y + height).min(oy + other_height).saturating_sub(y.max(oy));
+            let smaller = (width * height).min(other_width * other_height);
+            2 * overlap_width * overlap_height > smaller
+        });
+        if !overlaps {
+            kept.push(item);
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    
+    // Blocks of pseudo-random color, so every window is distinct and survives resizing
+    fn blocks(width: u32, height: u32, block: u32, seed: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            // MurmurHash3's finalizer over the block position
+            let mut hash = (x / block).wrapping_mul(73_856_093) ^ (y / block).wrapping_mul(19_349_663) ^ seed.wrapping_mul(83_492_791);
+            hash ^= hash >> 16;
+            hash = hash.wrapping_mul(0x85eb_ca6b);
+            hash ^= hash >> 13;
+            hash = hash.wrapping_mul(0xc2b2_ae35);
+            hash ^= hash >> 16;
+            Rgba([hash as u8, (hash >> 8) as u8, (hash >> 16) as u8, 255])
+        })
+    }
+    
+    fn crop(image: &RgbaImage, x: u32, y: u32, width: u32, height: u32) -> RgbaImage {
+        image::imageops::crop_imm(image, x, y, width, height).to_image()
+    }
+    
+    #[test]
+    fn finds_an_exact_crop() {
+        let screen = blocks(240, 160, 6, 1);
+        let template = crop(&screen, 61, 37, 36, 30);
+        
+        let matches = find_template(&screen, &template, 0.9, false).unwrap();
+        let best = &matches[0];
+        assert_eq!((best.x, best.y, best.width, best.height), (61, 37, 36, 30));
+        assert_eq!(best.scale, 1.0);
+        assert!(best.score > 0.999);
+    }
+    
+    #[test]
+    fn finds_a_template_saved_at_another_scale() {
+        // Saved on a display at half the scale of the one searched now
+        let screen = blocks(240, 160, 8, 2);
+        let template = image::imageops::resize(&crop(&screen, 96, 48, 64, 48), 32, 24, FilterType::Triangle);
+        
+        let best = &find_template(&screen, &template, 0.8, false).unwrap()[0];
+        assert_eq!(best.scale, 2.0);
+        assert_eq!((best.width, best.height), (64, 48));
+        assert!(best.x.abs_diff(96) <= 1 && best.y.abs_diff(48) <= 1, "found at ({}, {})", best.x, best.y);
+    }
+    
+    #[test]
+    fn reports_every_copy_once() {
+        let mut screen = blocks(240, 160, 6, 3);
+        let template = blocks(30, 30, 6, 4);
+        image::imageops::replace(&mut screen, &template, 12, 18);
+        image::imageops::replace(&mut screen, &template, 150, 100);
+        
+        let mut found: Vec<(u32, u32)> = find_template(&screen, &template, 0.95, true).unwrap()
+            .into_iter()
+            .map(|found| (found.x, found.y))
+            .collect();
+        found.sort();
+        assert_eq!(found, vec![(12, 18), (150, 100)]);
+    }
+    
+    #[test]
+    fn all_reports_more_copies_than_the_candidate_limit() {
+        // A column of 60 identical icons on a plain background
+        let icon = blocks(24, 24, 6, 9);
+        let mut screen = RgbaImage::from_pixel(40, 60 * 32, Rgba([240, 240, 240, 255]));
+        for row in 0..60 {
+            image::imageops::replace(&mut screen, &icon, 8, row * 32 + 4);
+        }
+        
+        let at_full_size = |all: bool| {
+            find_template(&screen, &icon, 0.95, all).unwrap()
+                .into_iter()
+                .filter(|found| found.scale == 1.0)
+                .count()
+        };
+        assert_eq!(at_full_size(true), 60);
+        assert!(at_full_size(false) <= MAX_CANDIDATES);
+    }
+    
+    #[test]
+    fn finds_nothing_when_the_template_is_absent() {
+        let screen = blocks(240, 160, 6, 5);
+        let template = blocks(30, 30, 5, 6);
+        assert!(find_template(&screen, &template, 0.95, false).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn rejects_flat_and_oversized_templates() {
+        let screen = blocks(120, 80, 6, 7);
+        let flat = RgbaImage::from_pixel(20, 20, Rgba([90, 90, 90, 255]));
+        assert!(find_template(&screen, &flat, 0.9, false).is_err());
+        
+        // Even at the smallest scale it doesn't fit
+        let huge = blocks(400, 400, 6, 8);
+        assert!(find_template(&screen, &huge, 0.9, false).is_err());
+    }
+    
+    #[test]
+    fn overlapping_matches_keep_the_first() {
+        // (2, 2) covers 64% of (0, 0) and goes; (8, 0) covers 20% and stays
+        let sorted = vec![(0, 0), (2, 2), (8, 0), (20, 20)];
+        let kept = suppress_overlaps(sorted, |&(x, y)| (x, y, 10, 10));
+        assert_eq!(kept, vec![(0, 0), (8, 0), (20, 20)]);
+    }
+}
@@ -1,3 +1,21 @@
+pub mod budget;
 pub mod capture;
+pub mod diff;
+pub mod fingerprint;
+pub mod matching;
+pub mod ocr;
+pub mod settle;
+pub mod store;
+pub mod templates;
+pub mod window;
 
-pub use capture::ScreenshotCapture;
\ No newline at end of file
+pub use budget::{parse_byte_size, EncodedImage, ImageBudget};
+pub use capture::ScreenshotCapture;
+pub use diff::{heatmap, ImageDiff};
+pub use fingerprint::{hash_distance, perceptual_hash, DisplayGeometry, ScreenFingerprint};
+pub use matching::find_template;
+pub use ocr::{find_phrase, recognize, OcrWord};
+pub use settle::{parse_millis, wait_stable};
+pub use store::{parse_age, CaptureKind, GridParameters, RetentionPolicy, ScreenshotRecord, ScreenshotStore};
+pub use templates::{TemplateInfo, TemplateLibrary};
+pub use window::WindowCapture;
\ No newline at end of file
@@ -0,0 +1,244 @@
+use anyhow::Result;
+use crate::config::OcrConfig;
+use crate::screenshot::ScreenshotCapture;
+use image::imageops::FilterType;
+use image::RgbaImage;
+
+/// A word recognized in an image.
+#[derive(Debug, Clone)]
+pub struct OcrWord {
+    pub text: String,
+    pub x: u32,          // Bounding box, in pixels of the recognized image
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub confidence: f32, // 0 to 100
+    pub line: usize,     // Words with the same number share a text line, numbered in reading order
+}
+
+/// Recognize the words in `image` with the local tesseract, following `[ocr]`.
+/// Nothing leaves the machine: the image goes to tesseract's stdin.
+pub fn recognize(image: &RgbaImage, ocr: &OcrConfig) -> Result<Vec<OcrWord>> {
+    let scale = ocr.upscale as f64;
+    let input = if (scale - 1.0).abs() < f64::EPSILON {
+        image.clone()
+    } else {
+        let width = ((image.width() as f64 * scale).round() as u32).max(1);
+        let height = ((image.height() as f64 * scale).round() as u32).max(1);
+        image::imageops::resize(image, width, height, FilterType::CatmullRom)
+    };
+    
+    let tsv = run_tesseract(&ScreenshotCapture::encode_image(&input, "png", None)?, ocr)?;
+    let words = parse_tsv(&tsv)?
+        .into_iter()
+        .filter(|word| word.confidence >= ocr.min_confidence)
+        .map(|word| {
+            // Back to the caller's pixels, staying inside the image
+            let x = ((word.x as f64 / scale).floor() as u32).min(image.width().saturating_sub(1));
+            let y = ((word.y as f64 / scale).floor() as u32).min(image.height().saturating_sub(1));
+            OcrWord {
+                x,
+                y,
+                width: ((word.width as f64 / scale).ceil() as u32).clamp(1, image.width() - x),
+                height: ((word.height as f64 / scale).ceil() as u32).clamp(1, image.height() - y),
+                ..word
+            }
+        })
+        .collect();
+    Ok(words)
+}
+
+/// Every place where the words of `phrase` appear in a row on one line,
+/// ignoring case and punctuation around words. Each result spans the matched
+/// words, with their text joined and the lowest confidence among them.
+pub fn find_phrase(words: &[OcrWord], phrase: &str) -> Vec<OcrWord> {
+    let wanted: Vec<String> = phrase.split_whitespace()
+        .map(normalize)
+        .filter(|word| !word.is_empty())
+        .collect();
+    if wanted.is_empty() || words.len() < wanted.len() {
+        return Vec::new();
+    }
+    
+    words.windows(wanted.len())
+        .filter(|run| {
+            run.iter().all(|word| word.line == run[0].line)
+                && run.iter().zip(&wanted).all(|(word, wanted)| normalize(&word.text) == *wanted)
+        })
+        .map(|run| {
+            let left = run.iter().map(|word| word.x).min().unwrap_or(0);
+            let top = run.iter().map(|word| word.y).min().unwrap_or(0);
+            let right = run.iter().map(|word| word.x + word.width).max().unwrap_or(left);
+            let bottom = run.iter().map(|word| word.y + word.height).max().unwrap_or(top);
+            OcrWord {
+                text: run.iter().map(|word| word.text.as_str()).collect::<Vec<_>>().join(" "),
+                x: left,
+                y: top,
+                width: right - left,
+                height: bottom - top,
+                confidence: run.iter().map(|word| word.confidence).fold(f32::MAX, f32::min),
+                line: run[0].line,
+            }
+        })
+        .collect()
+}
+
+fn normalize(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase()
+}
+
+#[cfg(feature = "ocr")]
+fn run_tesseract(png: &[u8], ocr: &OcrConfig) -> Result<String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    
+    let page_mode = ocr.page_mode.to_string();
+    let mut child = Command::new(&ocr.command)
+        .args(["stdin", "stdout", "-l", &ocr.language, "--psm", &page_mode, "tsv"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!(
+            "Failed to run '{}': {}. Install tesseract (e.g. 'apt install tesseract-ocr') or set ocr.command",
+            ocr.command, e
+        ))?;
+    
+    // tesseract reads all of its input before writing anything
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(png)?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "tesseract failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(not(feature = "ocr"))]
+fn run_tesseract(_png: &[u8], _ocr: &OcrConfig) -> Result<String> {
+    Err(anyhow::anyhow!(
+        "OCR is not available: gui-helper was built without the 'ocr' feature. \
+         Rebuild with 'cargo build --release --features ocr' (needs tesseract installed)"
+    ))
+}
+
+// Words from tesseract's TSV output. Columns: level, page_num, block_num,
+// par_num, line_num, word_num, left, top, width, height, conf, text; words are
+// level 5 and come in reading order.
+fn parse_tsv(tsv: &str) -> Result<Vec<OcrWord>> {
+    let mut words = Vec::new();
+    let mut current_line = None;
+    let mut line = 0;
+    
+    for row in tsv.lines().skip(1) {
+        let columns: Vec<&str> = row.splitn(12, '\t').collect();
+        if columns.len() < 12 || columns[0] != "5" || columns[11].trim().is_empty() {
+            continue;
+        }
+        
+        let number = |index: usize| -> Result<u32> {
+            columns[index].parse()
+                .map_err(|_| anyhow::anyhow!("Unexpected tesseract output: {:?}", row))
+        };
+        let line_key = (number(1)?, number(2)?, number(3)?, number(4)?);
+        if current_line.is_some_and(|current| current != line_key) {
+            line += 1;
+        }
+        current_line = Some(line_key);
+        
+        words.push(OcrWord {
+            text: columns[11].trim().to_string(),
+            x: number(6)?,
+            y: number(7)?,
+            width: number(8)?,
+            height: number(9)?,
+            confidence: columns[10].parse()
+                .map_err(|_| anyhow::anyhow!("Unexpected tesseract output: {:?}", row))?,
+            line,
+        });
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const HEADER: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext";
+    
+    fn word(text: &str, x: u32, line: usize) -> OcrWord {
+        OcrWord { text: text.to_string(), x, y: 10 * line as u32, width: 20, height: 8, confidence: 90.0, line }
+    }
+    
+    #[test]
+    fn parses_words_and_numbers_lines_in_order() {
+        let tsv = [
+            HEADER,
+            "1\t1\t0\t0\t0\t0\t0\t0\t800\t600\t-1\t",
+            "4\t1\t1\t1\t1\t0\t10\t10\t200\t20\t-1\t",
+            "5\t1\t1\t1\t1\t1\t10\t10\t60\t20\t96.5\tSave",
+            "5\t1\t1\t1\t1\t2\t80\t10\t40\t20\t91\tAs",
+            "5\t1\t1\t1\t2\t1\t10\t40\t50\t20\t88\tCancel",
+            "5\t1\t2\t1\t1\t1\t300\t40\t30\t20\t-1\t ",
+            "5\t1\t2\t1\t1\t1\t300\t40\t30\t20\t75\tOK",
+        ].join("\n");
+        
+        let words = parse_tsv(&tsv).unwrap();
+        let summary: Vec<(&str, usize)> = words.iter().map(|word| (word.text.as_str(), word.line)).collect();
+        assert_eq!(summary, vec![("Save", 0), ("As", 0), ("Cancel", 1), ("OK", 2)]);
+        
+        let save = &words[0];
+        assert_eq!((save.x, save.y, save.width, save.height), (10, 10, 60, 20));
+        assert_eq!(save.confidence, 96.5);
+    }
+    
+    #[test]
+    fn keeps_text_with_spaces_in_the_last_column() {
+        let tsv = format!("{}\n5\t1\t1\t1\t1\t1\t0\t0\t10\t10\t90\ta\tb", HEADER);
+        assert_eq!(parse_tsv(&tsv).unwrap()[0].text, "a\tb");
+    }
+    
+    #[test]
+    fn rejects_malformed_numbers() {
+        let tsv = format!("{}\n5\t1\t1\t1\t1\t1\tx\t0\t10\t10\t90\tword", HEADER);
+        assert!(parse_tsv(&tsv).is_err());
+        let tsv = format!("{}\n5\t1\t1\t1\t1\t1\t0\t0\t10\t10\thigh\tword", HEADER);
+        assert!(parse_tsv(&tsv).is_err());
+        assert!(parse_tsv("").unwrap().is_empty());
+    }
+    
+    #[test]
+    fn finds_phrases_ignoring_case_and_punctuation() {
+        let words = vec![word("File", 0, 0), word("Save", 30, 0), word("as...", 60, 0), word("Save", 0, 1)];
+        
+        let found = find_phrase(&words, "save AS");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].text, "Save as...");
+        assert_eq!((found[0].x, found[0].width), (30, 50));
+        
+        assert_eq!(find_phrase(&words, "save").len(), 2);
+        assert!(find_phrase(&words, "  ...  ").is_empty());
+    }
+    
+    #[test]
+    fn phrases_do_not_span_lines() {
+        let words = vec![word("Save", 0, 0), word("As", 0, 1)];
+        assert!(find_phrase(&words, "Save As").is_empty());
+    }
+    
+    #[test]
+    fn phrase_matches_take_the_lowest_confidence() {
+        let mut words = vec![word("Open", 0, 0), word("File", 30, 0)];
+        words[1].confidence = 61.0;
+        words[1].height = 12;
+        
+        let found = &find_phrase(&words, "open file")[0];
+        assert_eq!(found.confidence, 61.0);
+        assert_eq!((found.y, found.height), (0, 12));
+    }
+}
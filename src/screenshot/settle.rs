@@ -0,0 +1,118 @@
+use anyhow::Result;
+use crate::config::SettleConfig;
+use crate::screenshot::{ImageDiff, ScreenshotCapture};
+use image::imageops::FilterType;
+use image::RgbaImage;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Frames are shrunk to at most this width before comparing; small changes
+/// still show up and capture noise is smoothed out.
+const SAMPLE_WIDTH: u32 = 320;
+
+/// Outcome of `wait_stable`.
+#[derive(Debug, Clone)]
+pub struct Settled {
+    pub stable: bool,      // False when the timeout hit first
+    pub elapsed: Duration,
+    pub captures: u32,
+    pub last_score: f64,   // Changed fraction in the last comparison
+}
+
+/// Capture `region` (x, y, width, height; the whole screen when `None`) every
+/// `settle.interval` ms until `settle.frames` consecutive captures each differ
+/// from the previous one by less than `settle.threshold`, or `settle.timeout` passes.
+pub fn wait_stable(region: Option<(u32, u32, u32, u32)>, settle: &SettleConfig) -> Result<Settled> {
+    let capture = || -> Result<RgbaImage> {
+        let frame = match region {
+            Some((x, y, width, height)) => ScreenshotCapture::capture_area(x, y, width, height)?,
+            None => ScreenshotCapture::capture_screen()?,
+        };
+        Ok(sample(&frame))
+    };
+    
+    let start = Instant::now();
+    let timeout = Duration::from_millis(settle.timeout);
+    let mut previous = capture()?;
+    let mut captures = 1;
+    let mut quiet_frames = 0;
+    let mut last_score = 0.0;
+    
+    while quiet_frames < settle.frames {
+        if start.elapsed() >= timeout {
+            return Ok(Settled { stable: false, elapsed: start.elapsed(), captures, last_score });
+        }
+        thread::sleep(Duration::from_millis(settle.interval));
+        
+        let current = capture()?;
+        captures += 1;
+        let diff = ImageDiff::compute(&previous, &current)?;
+        last_score = diff.score;
+        if diff.changed(settle.threshold) {
+            quiet_frames = 0;
+        } else {
+            quiet_frames += 1;
+        }
+        previous = current;
+    }
+    
+    Ok(Settled { stable: true, elapsed: start.elapsed(), captures, last_score })
+}
+
+/// Parse waits like `500ms`, `10s` or `2m` into milliseconds. A bare number is milliseconds.
+pub fn parse_millis(wait: &str) -> Result<u64> {
+    let wait = wait.trim();
+    let split = wait.find(|c: char| !c.is_ascii_digit()).unwrap_or(wait.len());
+    let (number, unit) = wait.split_at(split);
+    
+    let number: u64 = number.parse()
+        .map_err(|_| anyhow::anyhow!("Invalid duration '{}'. Expected e.g. 500ms, 10s or 2m", wait))?;
+    
+    let multiplier = match unit {
+        "" | "ms" => 1,
+        "s" => 1000,
+        "m" => 60 * 1000,
+        _ => return Err(anyhow::anyhow!("Invalid duration unit '{}' in '{}'. Use ms, s or m", unit, wait)),
+    };
+    
+    number.checked_mul(multiplier)
+        .ok_or_else(|| anyhow::anyhow!("Duration '{}' is too long", wait))
+}
+
+fn sample(frame: &RgbaImage) -> RgbaImage {
+    if frame.width() <= SAMPLE_WIDTH {
+        return frame.clone();
+    }
+    let height = (frame.height() as u64 * SAMPLE_WIDTH as u64 / frame.width() as u64).max(1) as u32;
+    image::imageops::resize(frame, SAMPLE_WIDTH, height, FilterType::Triangle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parses_waits_in_every_unit() {
+        assert_eq!(parse_millis("250").unwrap(), 250);
+        assert_eq!(parse_millis("500ms").unwrap(), 500);
+        assert_eq!(parse_millis(" 10s ").unwrap(), 10_000);
+        assert_eq!(parse_millis("2m").unwrap(), 120_000);
+    }
+    
+    #[test]
+    fn rejects_invalid_waits() {
+        for wait in ["", "ms", "1.5s", "-1s", "10h", "10 s"] {
+            assert!(parse_millis(wait).is_err(), "{:?}", wait);
+        }
+        assert!(parse_millis(&format!("{}m", u64::MAX)).is_err());
+    }
+    
+    #[test]
+    fn samples_wide_frames_down_keeping_the_aspect_ratio() {
+        let small = RgbaImage::new(200, 100);
+        assert_eq!(sample(&small).dimensions(), (200, 100));
+        
+        let wide = RgbaImage::new(1920, 1080);
+        assert_eq!(sample(&wide).dimensions(), (SAMPLE_WIDTH, 180));
+    }
+}
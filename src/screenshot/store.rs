@@ -0,0 +1,497 @@
+use anyhow::Result;
+use crate::config::{Config, RetentionConfig};
+use crate::grid::{ScreenInfo, ZoomArea};
+use crate::screenshot::{parse_byte_size, ScreenshotCapture};
+use crate::session::SessionManager;
+use chrono::{DateTime, Utc};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const INDEX_FILENAME: &str = "index.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureKind {
+    Overview,
+    Zoom,
+    Screenshot,
+    Selection, // Overview with the zoomed square shaded
+    Receipt,   // Crop around a click target
+    Diff,      // Heatmap of what a verified click changed
+    Unknown,
+}
+
+/// Grid a capture was taken with, so it can be interpreted later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "level", rename_all = "lowercase")]
+pub enum GridParameters {
+    Overview(ScreenInfo),
+    Zoom(ZoomArea),
+}
+
+/// One entry of the screenshots directory index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenshotRecord {
+    pub filename: String,
+    pub kind: CaptureKind,
+    pub parent_square: Option<String>,
+    pub session_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub size_bytes: u64,
+    #[serde(default)]
+    pub command: Vec<String>,            // Command line that produced the capture
+    #[serde(default)]
+    pub grid: Option<GridParameters>,
+    #[serde(default)]
+    pub json_export: Option<String>,     // `--json` export written next to the image
+    #[serde(default)]
+    pub clean_copy: Option<String>,      // Grid-free copy from `save_clean_copy`
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    pub keep: Option<usize>,
+    pub older_than: Option<chrono::Duration>,
+    pub max_size: Option<u64>,
+}
+
+pub struct ScreenshotStore;
+
+impl ScreenshotRecord {
+    pub fn new(output_path: &Path, kind: CaptureKind, session_id: Option<String>) -> Result<Self> {
+        let filename = output_path.file_name()
+            .ok_or_else(|| anyhow::anyhow!("Invalid screenshot path: {:?}", output_path))?
+            .to_string_lossy()
+            .to_string();
+        
+        Ok(Self {
+            filename,
+            kind,
+            parent_square: None,
+            session_id,
+            created_at: Utc::now(),
+            size_bytes: fs::metadata(output_path)?.len(),
+            command: std::env::args().skip(1).collect(),
+            grid: None,
+            json_export: None,
+            clean_copy: None,
+        })
+    }
+    
+    pub fn with_grid(mut self, grid: GridParameters) -> Self {
+        if let GridParameters::Zoom(zoom_area) = &grid {
+            self.parent_square = Some(zoom_area.parent_square.clone());
+        }
+        self.grid = Some(grid);
+        self
+    }
+    
+    /// Note the clean copy saved with the capture; it counts toward its size.
+    pub fn with_clean_copy(mut self, clean_path: Option<&Path>) -> Result<Self> {
+        if let Some(clean_path) = clean_path {
+            self.size_bytes += fs::metadata(clean_path)?.len();
+            self.clean_copy = clean_path.file_name().map(|name| name.to_string_lossy().to_string());
+        }
+        Ok(self)
+    }
+}
+
+impl ScreenshotStore {
+    /// Add a freshly saved capture to the index, then apply the configured
+    /// retention policy. Returns the records that were pruned.
+    pub fn record(config: &Config, record: ScreenshotRecord) -> Result<Vec<ScreenshotRecord>> {
+        let screenshots_dir = SessionManager::get_screenshots_dir(config)?;
+        
+        {
+            let _lock = Self::lock_index(&screenshots_dir)?;
+            let mut records = Self::read_index(&screenshots_dir)?;
+            records.retain(|existing| existing.filename != record.filename);
+            records.push(record);
+            Self::write_index(&screenshots_dir, &records)?;
+        }
+        
+        match RetentionPolicy::from_config(&config.retention)? {
+            Some(policy) => Self::prune(config, &policy, false),
+            None => Ok(vec![]),
+        }
+    }
+    
+    /// Save `image` losslessly next to `output_path` as `<name>.clean.png`.
+    /// Overview and zoom captures keep such a copy without grid lines when the
+    /// saved image has them drawn in or is JPEG, so templates can be cropped
+    /// from the screen as it was.
+    pub fn save_clean_copy(image: &RgbaImage, output_path: &Path) -> Result<PathBuf> {
+        let stem = output_path.file_stem()
+            .ok_or_else(|| anyhow::anyhow!("Invalid screenshot path: {:?}", output_path))?;
+        let clean_path = output_path.with_file_name(format!("{}.clean.png", stem.to_string_lossy()));
+        fs::write(&clean_path, ScreenshotCapture::encode_image(image, "png", None)?)?;
+        Ok(clean_path)
+    }
+    
+    /// Note the `--json` export written next to an indexed capture, so pruning
+    /// the capture removes it too.
+    pub fn record_export(config: &Config, image_path: &Path, export_path: &Path) -> Result<()> {
+        let screenshots_dir = SessionManager::get_screenshots_dir(config)?;
+        let (image_name, export_name) = match (image_path.file_name(), export_path.file_name()) {
+            (Some(image_name), Some(export_name)) => (image_name.to_string_lossy(), export_name.to_string_lossy()),
+            _ => return Err(anyhow::anyhow!("Invalid export path: {:?}", export_path)),
+        };
+        
+        let _lock = Self::lock_index(&screenshots_dir)?;
+        let mut records = Self::read_index(&screenshots_dir)?;
+        if let Some(record) = records.iter_mut().find(|record| record.filename == image_name) {
+            record.json_export = Some(export_name.to_string());
+            Self::write_index(&screenshots_dir, &records)?;
+        }
+        Ok(())
+    }
+    
+    /// All screenshots on disk, newest first. Captures with a generated name
+    /// that are missing from the index (e.g. from older versions) are described
+    /// from their name and mtime; other files in the directory are not ours.
+    pub fn list(config: &Config) -> Result<Vec<ScreenshotRecord>> {
+        let screenshots_dir = SessionManager::get_screenshots_dir(config)?;
+        
+        let mut records: Vec<ScreenshotRecord> = Self::read_index(&screenshots_dir)?
+            .into_iter()
+            .filter(|record| screenshots_dir.join(&record.filename).exists())
+            .collect();
+        let indexed: HashSet<String> = records.iter().map(|record| record.filename.clone()).collect();
+        
+        for entry in fs::read_dir(&screenshots_dir)?.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let filename = entry.file_name().to_string_lossy().to_string();
+            
+            if !Self::is_image(&path) || indexed.contains(&filename) || !Self::is_generated_name(&filename) {
+                continue;
+            }
+            
+            let metadata = entry.metadata()?;
+            let (kind, parent_square) = Self::infer_kind(&filename);
+            records.push(ScreenshotRecord {
+                filename,
+                kind,
+                parent_square,
+                session_id: None,
+                created_at: metadata.modified().map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now()),
+                size_bytes: metadata.len(),
+                command: vec![],
+                grid: None,
+                json_export: None,
+                clean_copy: None,
+            });
+        }
+        
+        records.sort_by_key(|record| Reverse(record.created_at));
+        Ok(records)
+    }
+    
+    pub fn latest(config: &Config) -> Result<Option<PathBuf>> {
+        let screenshots_dir = SessionManager::get_screenshots_dir(config)?;
+        Ok(Self::list(config)?.first().map(|record| screenshots_dir.join(&record.filename)))
+    }
+    
+    /// Delete screenshots that fall outside `policy`, together with their
+    /// `--json` exports. Only captures from `list` are candidates, and images a
+    /// stored session still points to are kept without counting toward the
+    /// limits. With `dry_run`, only report what would be removed.
+    pub fn prune(config: &Config, policy: &RetentionPolicy, dry_run: bool) -> Result<Vec<ScreenshotRecord>> {
+        let screenshots_dir = SessionManager::get_screenshots_dir(config)?;
+        let referenced = Self::referenced_by_sessions()?;
+        let now = Utc::now();
+        
+        let mut kept = Vec::new();
+        let mut removed = Vec::new();
+        let mut kept_size = 0u64;
+        
+        // Newest first, so the most recent captures survive every limit
+        for record in Self::list(config)? {
+            if referenced.contains(&record.filename) {
+                continue;
+            }
+            
+            let beyond_count = policy.keep.is_some_and(|keep| kept.len() >= keep);
+            let too_old = policy.older_than.is_some_and(|age| now.signed_duration_since(record.created_at) > age);
+            let over_quota = policy.max_size.is_some_and(|max_size| kept_size + record.size_bytes > max_size);
+            
+            if beyond_count || too_old || over_quota {
+                removed.push(record);
+            } else {
+                kept_size += record.size_bytes;
+                kept.push(record);
+            }
+        }
+        
+        if dry_run {
+            return Ok(removed);
+        }
+        
+        for record in &removed {
+            let path = screenshots_dir.join(&record.filename);
+            fs::remove_file(&path)?;
+            
+            // Only the files we wrote; other JSON files may belong to the user
+            let companions = [&record.json_export, &record.clean_copy]
+                .into_iter()
+                .flatten()
+                .filter(|name| name.as_str() != INDEX_FILENAME);
+            for companion in companions {
+                let companion_path = screenshots_dir.join(companion);
+                if companion_path.exists() {
+                    fs::remove_file(companion_path)?;
+                }
+            }
+        }
+        
+        let removed_names: HashSet<&str> = removed.iter().map(|record| record.filename.as_str()).collect();
+        let _lock = Self::lock_index(&screenshots_dir)?;
+        let remaining: Vec<ScreenshotRecord> = Self::read_index(&screenshots_dir)?
+            .into_iter()
+            .filter(|record| !removed_names.contains(record.filename.as_str()))
+            .filter(|record| screenshots_dir.join(&record.filename).exists())
+            .collect();
+        Self::write_index(&screenshots_dir, &remaining)?;
+        
+        Ok(removed)
+    }
+    
+    fn read_index(screenshots_dir: &Path) -> Result<Vec<ScreenshotRecord>> {
+        let index_path = screenshots_dir.join(INDEX_FILENAME);
+        
+        if !index_path.exists() {
+            return Ok(vec![]);
+        }
+        
+        let contents = fs::read_to_string(&index_path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+    
+    // Callers must hold the index lock. Written to a temporary file first so
+    // readers never see a partial index.
+    fn write_index(screenshots_dir: &Path, records: &[ScreenshotRecord]) -> Result<()> {
+        let json_data = serde_json::to_string_pretty(records)?;
+        let index_path = screenshots_dir.join(INDEX_FILENAME);
+        let temp_path = index_path.with_extension("json.tmp");
+        fs::write(&temp_path, json_data)?;
+        fs::rename(&temp_path, &index_path)?;
+        Ok(())
+    }
+    
+    // Exclusive lock on the index, released when dropped. Hold it from reading
+    // the index until writing it back, or concurrent captures lose records.
+    fn lock_index(screenshots_dir: &Path) -> Result<fs::File> {
+        let lock_path = screenshots_dir.join(INDEX_FILENAME).with_extension("json.lock");
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)?;
+        file.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock the screenshot index {:?}: {}", lock_path, e))?;
+        Ok(file)
+    }
+    
+    // File names of every image a stored session refers to, now or in its history
+    fn referenced_by_sessions() -> Result<HashSet<String>> {
+        let mut referenced = HashSet::new();
+        for session_data in SessionManager::list_sessions()?.into_iter().filter_map(|entry| entry.data) {
+            let images = [&session_data.overview_image, &session_data.zoom_image]
+                .into_iter()
+                .flatten()
+                .chain(session_data.history.iter().filter_map(|step| step.zoom_image.as_ref()))
+                .filter_map(|image| image.path.clone());
+            let steps = session_data.history.iter().filter_map(|step| step.screenshot.clone());
+            
+            for path in images.chain(steps) {
+                if let Some(filename) = path.file_name() {
+                    referenced.insert(filename.to_string_lossy().to_string());
+                }
+            }
+        }
+        Ok(referenced)
+    }
+    
+    fn is_image(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| matches!(ext.to_lowercase().as_str(), "png" | "jpg" | "jpeg" | "webp"))
+            .unwrap_or(false)
+    }
+    
+    // Whether `filename` is one `SessionManager::create_screenshot_file`
+    // makes: a known prefix, then `_<YYYY-MM-DD>_<HH-MM-SS-mmm>` and an
+    // optional `-<counter>`
+    fn is_generated_name(filename: &str) -> bool {
+        if Self::infer_kind(filename).0 == CaptureKind::Unknown {
+            return false;
+        }
+        
+        let stem = Path::new(filename).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let (date, time) = match stem.rsplitn(3, '_').collect::<Vec<_>>()[..] {
+            [time, date, _] => (date.to_string(), time.to_string()),
+            _ => return false,
+        };
+        
+        // Each `-`-separated field is all digits, with these lengths (0: any)
+        let fields_match = |text: &str, lengths: &[usize]| {
+            let fields: Vec<&str> = text.split('-').collect();
+            fields.len() == lengths.len()
+                && fields.iter().zip(lengths).all(|(field, &length)| {
+                    let length_ok = if length == 0 { !field.is_empty() } else { field.len() == length };
+                    length_ok && field.chars().all(|c| c.is_ascii_digit())
+                })
+        };
+        fields_match(&date, &[4, 2, 2])
+            && (fields_match(&time, &[2, 2, 2, 3]) || fields_match(&time, &[2, 2, 2, 3, 0]))
+    }
+    
+    // Generated names look like `overview_<time>.png`, `zoom_B5_<time>.png`
+    // or `click_B5_23_<time>.png` (`diff_` for click heatmaps)
+    fn infer_kind(filename: &str) -> (CaptureKind, Option<String>) {
+        let square = |rest: &str| rest.split('_').next().map(|square| square.to_string());
+        if filename.starts_with("overview_") {
+            (CaptureKind::Overview, None)
+        } else if let Some(rest) = filename.strip_prefix("zoom_") {
+            (CaptureKind::Zoom, square(rest))
+        } else if let Some(rest) = filename.strip_prefix("selection_") {
+            (CaptureKind::Selection, square(rest))
+        } else if let Some(rest) = filename.strip_prefix("click_") {
+            (CaptureKind::Receipt, square(rest))
+        } else if let Some(rest) = filename.strip_prefix("diff_") {
+            (CaptureKind::Diff, square(rest))
+        } else if filename.starts_with("screenshot_") {
+            (CaptureKind::Screenshot, None)
+        } else {
+            (CaptureKind::Unknown, None)
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// The automatic policy from config, or `None` when no limit is set.
+    pub fn from_config(retention: &RetentionConfig) -> Result<Option<Self>> {
+        let policy = Self {
+            keep: retention.keep,
+            older_than: retention.older_than.as_deref().map(parse_age).transpose()?,
+            max_size: retention.max_size.as_deref().map(parse_byte_size).transpose()?,
+        };
+        
+        if policy.keep.is_none() && policy.older_than.is_none() && policy.max_size.is_none() {
+            Ok(None)
+        } else {
+            Ok(Some(policy))
+        }
+    }
+}
+
+/// Parse ages like `30m`, `12h`, `7d` or `2w`.
+pub fn parse_age(age: &str) -> Result<chrono::Duration> {
+    let age = age.trim();
+    let split = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let (number, unit) = age.split_at(split);
+    
+    let number: i64 = number.parse()
+        .map_err(|_| anyhow::anyhow!("Invalid age '{}'. Expected e.g. 30m, 12h, 7d or 2w", age))?;
+    
+    let duration = match unit {
+        "s" => chrono::Duration::try_seconds(number),
+        "m" => chrono::Duration::try_minutes(number),
+        "h" => chrono::Duration::try_hours(number),
+        "d" => chrono::Duration::try_days(number),
+        "w" => chrono::Duration::try_weeks(number),
+        _ => return Err(anyhow::anyhow!("Invalid age unit '{}' in '{}'. Use s, m, h, d or w", unit, age)),
+    };
+    duration.ok_or_else(|| anyhow::anyhow!("Age '{}' is too large", age))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parses_ages_in_every_unit() {
+        assert_eq!(parse_age("90s").unwrap(), chrono::Duration::seconds(90));
+        assert_eq!(parse_age("30m").unwrap(), chrono::Duration::minutes(30));
+        assert_eq!(parse_age(" 12h ").unwrap(), chrono::Duration::hours(12));
+        assert_eq!(parse_age("7d").unwrap(), chrono::Duration::days(7));
+        assert_eq!(parse_age("2w").unwrap(), chrono::Duration::weeks(2));
+    }
+    
+    #[test]
+    fn rejects_invalid_ages() {
+        assert!(parse_age("").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("7").is_err());
+        assert!(parse_age("7y").is_err());
+        assert!(parse_age("-7d").is_err());
+    }
+    
+    #[test]
+    fn rejects_ages_that_overflow() {
+        assert!(parse_age("9223372036854775807w").is_err());
+        assert!(parse_age("99999999999999999999d").is_err());
+    }
+    
+    #[test]
+    fn infers_the_kind_and_square_from_generated_names() {
+        assert_eq!(ScreenshotStore::infer_kind("overview_2026-01-02_03-04-05-006.png"), (CaptureKind::Overview, None));
+        assert_eq!(ScreenshotStore::infer_kind("zoom_B5_2026-01-02_03-04-05-006.png"), (CaptureKind::Zoom, Some("B5".to_string())));
+        assert_eq!(ScreenshotStore::infer_kind("click_C3_2026-01-02_03-04-05-006.png"), (CaptureKind::Receipt, Some("C3".to_string())));
+        assert_eq!(ScreenshotStore::infer_kind("holiday.png"), (CaptureKind::Unknown, None));
+    }
+    
+    #[test]
+    fn recognizes_generated_names_only() {
+        assert!(ScreenshotStore::is_generated_name("overview_2026-01-02_03-04-05-006.png"));
+        assert!(ScreenshotStore::is_generated_name("zoom_B5_2026-01-02_03-04-05-006.jpg"));
+        assert!(ScreenshotStore::is_generated_name("selection_B5_2026-01-02_03-04-05-006-12.webp"));
+        
+        assert!(!ScreenshotStore::is_generated_name("holiday.png"));
+        assert!(!ScreenshotStore::is_generated_name("overview_final.png"));
+        assert!(!ScreenshotStore::is_generated_name("overview_2026-01-02.png"));
+        assert!(!ScreenshotStore::is_generated_name("zoom_B5_2026-1-02_03-04-05-006.png"));
+        assert!(!ScreenshotStore::is_generated_name("zoom_B5_2026-01-02_03-04-05-006-.png"));
+        assert!(!ScreenshotStore::is_generated_name("zoom_B5_2026-01-02_03-04-05-006-x.png"));
+    }
+    
+    #[test]
+    fn generated_names_are_recognized() {
+        let dir = std::env::temp_dir();
+        for prefix in ["overview", "zoom_B5", "selection_B5", "click_B5", "diff_B5", "screenshot"] {
+            let (path, _) = SessionManager::create_screenshot_file(&dir, prefix, "png").unwrap();
+            fs::remove_file(&path).unwrap();
+            let filename = path.file_name().unwrap().to_string_lossy();
+            assert!(ScreenshotStore::is_generated_name(&filename), "{}", filename);
+        }
+    }
+    
+    #[test]
+    fn concurrent_records_are_all_kept() {
+        let dir = std::env::temp_dir().join(format!("gui-helper-index-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut config = Config::default();
+        config.display.screenshot_dir = dir.to_string_lossy().to_string();
+        config.retention = RetentionConfig::default();
+        
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let (config, dir) = (config.clone(), dir.clone());
+                std::thread::spawn(move || {
+                    let (path, _) = SessionManager::create_screenshot_file(&dir, "screenshot", "png").unwrap();
+                    ScreenshotStore::record(&config, ScreenshotRecord::new(&path, CaptureKind::Screenshot, None).unwrap()).unwrap();
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        
+        let records = ScreenshotStore::read_index(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(records.len(), 8);
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use crate::config::Config;
+use chrono::{DateTime, Utc};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where a template came from, saved next to its image as `<name>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub width: u32,                   // Stored image size in pixels
+    pub height: u32,
+    pub source: String,               // "B5.23", "B5" or "region x,y,w,h"
+    pub screen_x: u32,                // Screen rectangle that was cropped
+    pub screen_y: u32,
+    pub screen_width: u32,
+    pub screen_height: u32,
+    pub source_image: Option<String>, // Screenshot it was cut from; `None` when captured from the screen
+    pub window: Option<String>,       // Title of the window under the crop, if known
+    pub image_scale: f64,             // Image pixels per screen pixel of the source
+    pub display_scale: Option<f32>,   // Scale factor (DPI) of the display it was on
+    pub session_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Saved templates for `find --template`, under `<data dir>/templates`.
+pub struct TemplateLibrary;
+
+impl TemplateLibrary {
+    pub fn dir() -> Result<PathBuf> {
+        Ok(Config::data_dir()?.join("templates"))
+    }
+    
+    /// Image path of template `name`, whether or not it exists.
+    pub fn image_path(name: &str) -> Result<PathBuf> {
+        Ok(Self::dir()?.join(format!("{}.png", Self::validate_name(name)?)))
+    }
+    
+    pub fn save(image: &RgbaImage, info: &TemplateInfo, force: bool) -> Result<PathBuf> {
+        let image_path = Self::image_path(&info.name)?;
+        if image_path.exists() && !force {
+            return Err(anyhow::anyhow!(
+                "Template '{}' already exists. Pass --force to replace it.",
+                info.name
+            ));
+        }
+        
+        fs::create_dir_all(Self::dir()?)?;
+        image.save(&image_path)
+            .map_err(|e| anyhow::anyhow!("Failed to save template {:?}: {}", image_path, e))?;
+        fs::write(image_path.with_extension("json"), serde_json::to_string_pretty(info)?)?;
+        Ok(image_path)
+    }
+    
+    /// All templates, sorted by name. Images without metadata are skipped.
+    pub fn list() -> Result<Vec<TemplateInfo>> {
+        let dir = Self::dir()?;
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        
+        let mut templates = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") || !path.with_extension("png").exists() {
+                continue;
+            }
+            match Self::read_info(&path) {
+                Ok(info) => templates.push(info),
+                Err(e) => eprintln!("⚠ Skipping template metadata {:?}: {}", path, e),
+            }
+        }
+        
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+    
+    /// Remove a template's image and metadata. Returns false if it didn't exist.
+    pub fn delete(name: &str) -> Result<bool> {
+        let image_path = Self::image_path(name)?;
+        let metadata_path = image_path.with_extension("json");
+        let existed = image_path.exists() || metadata_path.exists();
+        
+        for path in [image_path, metadata_path] {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(existed)
+    }
+    
+    fn read_info(path: &Path) -> Result<TemplateInfo> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+    
+    /// Template names become file names, so only letters, digits, `-` and `_`
+    /// are allowed.
+    pub fn validate_name(name: &str) -> Result<&str> {
+        let valid = !name.is_empty()
+            && name.len() <= 64
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        
+        if valid {
+            Ok(name)
+        } else {
+            Err(anyhow::anyhow!(
+                "Invalid template name '{}': use up to 64 letters, digits, '-' or '_'",
+                name
+            ))
+        }
+    }
+}
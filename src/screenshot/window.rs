@@ -0,0 +1,169 @@
+use anyhow::Result;
+use crate::automation::WindowController;
+use crate::screenshot::ScreenshotCapture;
+use image::RgbaImage;
+use std::thread;
+use std::time::Duration;
+
+pub struct WindowCapture;
+
+impl WindowCapture {
+    /// Read the window's own contents from its composite pixmap.
+    ///
+    /// Only works on X11 with a compositing manager running, but captures the
+    /// window correctly even when other windows overlap it.
+    #[cfg(target_os = "linux")]
+    pub fn capture_direct(window_id: &str) -> Result<RgbaImage> {
+        use xcb::{composite, x};
+        
+        let raw_id = u32::from_str_radix(window_id.trim_start_matches("0x"), 16)
+            .map_err(|_| anyhow::anyhow!("Invalid window ID: {}", window_id))?;
+        
+        let (conn, _) = xcb::Connection::connect_with_extensions(None, &[xcb::Extension::Composite], &[])
+            .map_err(|e| anyhow::anyhow!("X11 Composite extension not available: {}", e))?;
+        
+        // The client must announce the version it speaks before using the extension
+        conn.wait_for_reply(conn.send_request(&composite::QueryVersion {
+            client_major_version: 0,
+            client_minor_version: 4,
+        }))?;
+        
+        // SAFETY: the ID names an existing window reported by the window manager
+        let client: x::Window = unsafe { xcb::XidNew::new(raw_id) };
+        
+        // A reparenting window manager puts the client inside a frame, and only
+        // the frame (the root's child) is redirected and has a pixmap
+        let frame = Self::top_level(&conn, client)?;
+        let pixmap: x::Pixmap = conn.generate_id();
+        
+        // Fails with BadMatch when the window is not redirected (no compositor)
+        conn.send_and_check_request(&composite::NameWindowPixmap { window: frame, pixmap })
+            .map_err(|e| anyhow::anyhow!("Window {} has no composite pixmap: {}", window_id, e))?;
+        
+        let result = Self::read_pixmap(&conn, pixmap)
+            .and_then(|image| Self::crop_client(&conn, client, frame, image));
+        conn.send_request(&x::FreePixmap { pixmap });
+        conn.flush()?;
+        
+        result
+    }
+    
+    // The ancestor of `window` that is a direct child of the root: the frame
+    // under a reparenting window manager, otherwise the window itself
+    #[cfg(target_os = "linux")]
+    fn top_level(conn: &xcb::Connection, window: xcb::x::Window) -> Result<xcb::x::Window> {
+        let mut current = window;
+        loop {
+            let tree = conn.wait_for_reply(conn.send_request(&xcb::x::QueryTree { window: current }))?;
+            if tree.parent() == tree.root() || tree.parent() == xcb::x::WINDOW_NONE {
+                return Ok(current);
+            }
+            current = tree.parent();
+        }
+    }
+    
+    // Cut the client's area out of its frame's pixmap, which also holds the
+    // title bar, the borders and the frame's own border
+    #[cfg(target_os = "linux")]
+    fn crop_client(conn: &xcb::Connection, client: xcb::x::Window, frame: xcb::x::Window, image: RgbaImage) -> Result<RgbaImage> {
+        use xcb::x;
+        
+        if client == frame {
+            return Ok(image);
+        }
+        
+        let frame_geometry = conn.wait_for_reply(conn.send_request(&x::GetGeometry {
+            drawable: x::Drawable::Window(frame),
+        }))?;
+        let client_geometry = conn.wait_for_reply(conn.send_request(&x::GetGeometry {
+            drawable: x::Drawable::Window(client),
+        }))?;
+        let offset = conn.wait_for_reply(conn.send_request(&x::TranslateCoordinates {
+            src_window: client,
+            dst_window: frame,
+            src_x: 0,
+            src_y: 0,
+        }))?;
+        
+        let border = frame_geometry.border_width() as i32;
+        let left = (offset.dst_x() as i32 + border).clamp(0, image.width() as i32) as u32;
+        let top = (offset.dst_y() as i32 + border).clamp(0, image.height() as i32) as u32;
+        let width = (client_geometry.width() as u32).min(image.width() - left);
+        let height = (client_geometry.height() as u32).min(image.height() - top);
+        if width == 0 || height == 0 {
+            return Err(anyhow::anyhow!("Window lies outside its frame's pixmap"));
+        }
+        
+        Ok(image::imageops::crop_imm(&image, left, top, width, height).to_image())
+    }
+    
+    #[cfg(not(target_os = "linux"))]
+    pub fn capture_direct(_window_id: &str) -> Result<RgbaImage> {
+        Err(anyhow::anyhow!("Direct window capture is only supported on X11"))
+    }
+    
+    #[cfg(target_os = "linux")]
+    fn read_pixmap(conn: &xcb::Connection, pixmap: xcb::x::Pixmap) -> Result<RgbaImage> {
+        use xcb::x;
+        
+        let geometry = conn.wait_for_reply(conn.send_request(&x::GetGeometry {
+            drawable: x::Drawable::Pixmap(pixmap),
+        }))?;
+        let (width, height) = (geometry.width() as u32, geometry.height() as u32);
+        
+        let reply = conn.wait_for_reply(conn.send_request(&x::GetImage {
+            format: x::ImageFormat::ZPixmap,
+            drawable: x::Drawable::Pixmap(pixmap),
+            x: 0,
+            y: 0,
+            width: width as u16,
+            height: height as u16,
+            plane_mask: u32::MAX,
+        }))?;
+        
+        if !matches!(reply.depth(), 24 | 32) {
+            return Err(anyhow::anyhow!("Unsupported window depth: {}", reply.depth()));
+        }
+        
+        // 24 and 32 bit visuals are both laid out as 4 bytes per pixel
+        let bgrx = reply.data();
+        let lsb_first = conn.get_setup().image_byte_order() == x::ImageOrder::LsbFirst;
+        let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+        
+        for pixel in bgrx.chunks_exact(4).take((width * height) as usize) {
+            if lsb_first {
+                rgba.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 255]);
+            } else {
+                rgba.extend_from_slice(&[pixel[1], pixel[2], pixel[3], 255]);
+            }
+        }
+        
+        RgbaImage::from_raw(width, height, rgba)
+            .ok_or_else(|| anyhow::anyhow!("Failed to create image buffer"))
+    }
+    
+    /// Bring the window to the front and grab its on-screen rectangle.
+    ///
+    /// Fallback for when the window's pixmap can't be read directly.
+    pub fn capture_raised(window_id: &str) -> Result<RgbaImage> {
+        WindowController::focus_window_by_id(window_id)?;
+        
+        // Give the window manager time to restack and the app time to repaint
+        thread::sleep(Duration::from_millis(300));
+        
+        let (x, y, width, height) = WindowController::window_geometry(window_id)?;
+        let (screen_width, screen_height) = ScreenshotCapture::get_primary_display_size()?;
+        
+        // Clip to the visible part of the primary display
+        let left = x.clamp(0, screen_width as i32) as u32;
+        let top = y.clamp(0, screen_height as i32) as u32;
+        let right = (x + width as i32).clamp(0, screen_width as i32) as u32;
+        let bottom = (y + height as i32).clamp(0, screen_height as i32) as u32;
+        
+        if right <= left || bottom <= top {
+            return Err(anyhow::anyhow!("Window {} is not visible on the primary display", window_id));
+        }
+        
+        ScreenshotCapture::capture_area(left, top, right - left, bottom - top)
+    }
+}
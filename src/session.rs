@@ -1,72 +1,411 @@
 use anyhow::Result;
-use crate::grid::SessionData;
-use crate::config::Config;
+use crate::grid::{SessionData, SESSION_VERSION};
+use crate::config::{Config, NewerVersion};
+use crate::screenshot::ScreenshotStore;
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
-use chrono::Utc;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use chrono::{DateTime, Utc};
 
 pub struct SessionManager;
 
+/// Name used when neither `--session` nor `GUI_HELPER_SESSION` is given.
+pub const DEFAULT_SESSION: &str = "default";
+
+/// Summary of one stored session for `session list`.
+#[derive(Debug)]
+pub struct SessionEntry {
+    pub name: String,
+    pub data: Option<SessionData>,    // None if the file could not be parsed
+}
+
+/// One command run against a session, kept for `session inspect` and bundles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionEntry {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: Option<String>, // Grid the command ran against
+    pub command: Vec<String>,       // Arguments after the program name
+    pub error: Option<String>,      // None if the command succeeded
+}
+
+/// Exclusive lock on a session, released when dropped.
+pub struct SessionLock {
+    _file: fs::File,
+    name: String,
+    loaded: Option<Revision>, // What `load_session_for_update` read under this lock
+}
+
+// Session ID, history length and current step: any change means the session
+// was replaced or moved on since it was read
+type Revision = (Option<String>, usize, Option<usize>);
+
 impl SessionManager {
-    pub fn save_session(session_data: &SessionData) -> Result<()> {
-        let session_path = Self::session_path()?;
-        
-        if let Some(parent) = session_path.parent() {
-            fs::create_dir_all(parent)?;
+    pub fn save_session(name: &str, session_data: &SessionData) -> Result<()> {
+        let _lock = Self::lock_session(name)?;
+        Self::write_session(name, session_data)
+    }
+    
+    pub fn load_session(config: &Config, name: &str) -> Result<Option<SessionData>> {
+        match Self::read_session(name)? {
+            // The file may have been replaced since it was read, so only expire
+            // what is still on disk once the lock is held
+            Some(session_data) if Self::is_expired(config, &session_data) => {
+                Ok(Self::load_session_for_update(config, name)?.1)
+            }
+            session_data => Ok(session_data),
         }
+    }
+    
+    /// Lock the session and load it, for commands that change it. Keep the lock
+    /// until `update_session_timestamp` so no other command writes in between.
+    pub fn load_session_for_update(config: &Config, name: &str) -> Result<(SessionLock, Option<SessionData>)> {
+        let mut lock = Self::lock_session(name)?;
+        let session_data = match Self::read_session_locked(name)? {
+            Some(session_data) if Self::is_expired(config, &session_data) => {
+                Self::expire_session(config, name)?;
+                None
+            }
+            session_data => session_data,
+        };
         
-        let json_data = serde_json::to_string_pretty(session_data)?;
-        fs::write(&session_path, json_data)?;
-        Ok(())
+        lock.loaded = session_data.as_ref().map(Self::revision);
+        Ok((lock, session_data))
+    }
+    
+    /// Whether the session is older than `session.ttl_minutes`. A TTL of 0 never expires.
+    pub fn is_expired(config: &Config, session_data: &SessionData) -> bool {
+        let ttl_minutes = config.session.ttl_minutes;
+        let session_age = Utc::now().signed_duration_since(session_data.timestamp);
+        ttl_minutes > 0 && session_age.num_minutes() > ttl_minutes as i64
+    }
+    
+    /// Read the session file without expiring it. A file that can't be parsed
+    /// is moved aside, but only under the session lock.
+    pub fn read_session(name: &str) -> Result<Option<SessionData>> {
+        match Self::read_session_file(name)? {
+            None => Ok(None),
+            Some((_, Ok(session_data))) => Ok(Some(session_data)),
+            Some((_, Err(e))) if e.is::<NewerVersion>() => {
+                Err(anyhow::anyhow!("Could not read session '{}': {}", name, e))
+            }
+            Some(_) => {
+                let _lock = Self::lock_session(name)?;
+                Self::read_session_locked(name)
+            }
+        }
     }
     
-    pub fn load_session() -> Result<Option<SessionData>> {
-        let session_path = Self::session_path()?;
+    // Like `read_session`, for callers that already hold the session lock
+    fn read_session_locked(name: &str) -> Result<Option<SessionData>> {
+        match Self::read_session_file(name)? {
+            None => Ok(None),
+            Some((_, Ok(session_data))) => Ok(Some(session_data)),
+            Some((_, Err(e))) if e.is::<NewerVersion>() => {
+                Err(anyhow::anyhow!("Could not read session '{}': {}", name, e))
+            }
+            Some((session_path, Err(e))) => {
+                // Treat an unreadable session like a missing one, but keep the file
+                let timestamp = Utc::now().format("%Y%m%d-%H%M%S");
+                let backup_path = session_path.with_extension(format!("json.invalid-{}.bak", timestamp));
+                fs::rename(&session_path, &backup_path)?;
+                eprintln!("⚠ Could not read session '{}': {}", name, e);
+                eprintln!("⚠ Starting without it; the old file was saved as {:?}", backup_path);
+                Ok(None)
+            }
+        }
+    }
+    
+    // The session file and the result of parsing it, or None if there is none
+    fn read_session_file(name: &str) -> Result<Option<(PathBuf, Result<SessionData>)>> {
+        let session_path = Self::session_path(name)?;
         
         if !session_path.exists() {
             return Ok(None);
         }
         
         let contents = fs::read_to_string(&session_path)?;
-        let session_data: SessionData = serde_json::from_str(&contents)?;
+        let session_data = Self::parse_session(&contents);
+        Ok(Some((session_path, session_data)))
+    }
+    
+    /// Parse a session file of any known version into the current layout.
+    /// Fails with `NewerVersion` for files from a newer gui-helper.
+    pub fn parse_session(contents: &str) -> Result<SessionData> {
+        let value: serde_json::Value = serde_json::from_str(contents)?;
+        let version = value.get("version")
+            .and_then(|version| version.as_u64())
+            .unwrap_or(0) as u32;
         
-        // Check if session is recent (within last hour)
-        let now = Utc::now();
-        let session_age = now.signed_duration_since(session_data.timestamp);
+        if version > SESSION_VERSION {
+            return Err(NewerVersion { file: "session".to_string(), version, supported: SESSION_VERSION }.into());
+        }
         
-        if session_age.num_minutes() > 60 {
-            // Session is too old, clear it
-            Self::clear_session()?;
-            return Ok(None);
+        let mut session_data: SessionData = serde_json::from_value(value)?;
+        Self::migrate_session(&mut session_data, version);
+        Ok(session_data)
+    }
+    
+    // One step per version; each step upgrades `from` to `from + 1`
+    fn migrate_session(session_data: &mut SessionData, from: u32) {
+        for version in from..SESSION_VERSION {
+            if version == 0 {
+                // Unversioned sessions had no ID or history. Seed the history with
+                // the stored grid so `session back` and bundles have a first step.
+                if session_data.session_id.is_none() {
+                    session_data.session_id = Some(session_data.timestamp.format("%Y%m%d-%H%M%S-%3f").to_string());
+                }
+                if session_data.history.is_empty() {
+                    let command = match &session_data.selected_square {
+                        Some(square) => format!("zoom {}", square),
+                        None => "overview".to_string(),
+                    };
+                    session_data.record_step(&command);
+                    session_data.history[0].timestamp = session_data.timestamp;
+                }
+            }
         }
         
-        Ok(Some(session_data))
+        session_data.version = SESSION_VERSION;
     }
     
-    pub fn clear_session() -> Result<()> {
-        let session_path = Self::session_path()?;
+    pub fn clear_session(name: &str) -> Result<()> {
+        let _lock = Self::lock_session(name)?;
+        Self::remove_session_files(name)
+    }
+    
+    // Session is too old, clear it. Callers must hold the session lock.
+    fn expire_session(config: &Config, name: &str) -> Result<()> {
+        eprintln!("⚠ Session '{}' expired (older than {} minutes) and was cleared",
+                  name, config.session.ttl_minutes);
+        Self::remove_session_files(name)
+    }
+    
+    // Callers must hold the session lock
+    fn remove_session_files(name: &str) -> Result<()> {
+        let session_path = Self::session_path(name)?;
         
         if session_path.exists() {
             fs::remove_file(&session_path)?;
         }
         
+        let actions_path = Self::actions_path(name)?;
+        if actions_path.exists() {
+            fs::remove_file(actions_path)?;
+        }
+        
         Ok(())
     }
     
-    pub fn update_session_timestamp(mut session_data: SessionData) -> Result<SessionData> {
+    /// Append the running command and its outcome to the session's action log.
+    pub fn log_action(name: &str, error: Option<String>) -> Result<()> {
+        let _lock = Self::lock_session(name)?;
+        
+        let entry = ActionEntry {
+            timestamp: Utc::now(),
+            session_id: Self::read_session_locked(name).ok().flatten().and_then(|session_data| session_data.session_id),
+            command: std::env::args().skip(1).collect(),
+            error,
+        };
+        
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::actions_path(name)?)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+    
+    /// The action log of a session, oldest first. Unreadable lines are skipped.
+    pub fn read_actions(name: &str) -> Result<Vec<ActionEntry>> {
+        let actions_path = Self::actions_path(name)?;
+        
+        if !actions_path.exists() {
+            return Ok(vec![]);
+        }
+        
+        Ok(fs::read_to_string(&actions_path)?
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+    
+    /// Replace the action log of a session, e.g. when importing a bundle.
+    pub fn write_actions(name: &str, actions: &[ActionEntry]) -> Result<()> {
+        let _lock = Self::lock_session(name)?;
+        
+        let mut contents = String::new();
+        for entry in actions {
+            contents.push_str(&serde_json::to_string(entry)?);
+            contents.push('\n');
+        }
+        
+        fs::write(Self::actions_path(name)?, contents)?;
+        Ok(())
+    }
+    
+    /// Store a session loaded with `load_session_for_update`, still under its
+    /// lock. Fails if the file no longer holds the session and step that was
+    /// loaded, e.g. because it was replaced by a new overview or cleared.
+    pub fn update_session_timestamp(lock: &SessionLock, mut session_data: SessionData) -> Result<SessionData> {
+        let name = &lock.name;
+        let current = Self::read_session_locked(name)?;
+        
+        match (current.as_ref().map(Self::revision), &lock.loaded) {
+            (Some(current), Some(loaded)) if current == *loaded => {}
+            (Some((session_id, ..)), Some((loaded_id, ..))) if session_id == *loaded_id => {
+                return Err(anyhow::anyhow!(
+                    "Session '{}' moved to another step since it was loaded. Run the command again.",
+                    name
+                ));
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Session '{}' was replaced by another command. Run 'gui-helper overview' again.",
+                    name
+                ));
+            }
+        }
+        
         session_data.timestamp = Utc::now();
-        Self::save_session(&session_data)?;
+        Self::write_session(name, &session_data)?;
         Ok(session_data)
     }
     
-    fn session_path() -> Result<PathBuf> {
-        let data_dir = Config::data_dir()?;
-        Ok(data_dir.join("session.json"))
+    fn revision(session_data: &SessionData) -> Revision {
+        (session_data.session_id.clone(), session_data.history.len(), session_data.current_step)
+    }
+    
+    /// All stored sessions, sorted by name.
+    pub fn list_sessions() -> Result<Vec<SessionEntry>> {
+        Self::migrate_legacy_session()?;
+        let sessions_dir = Self::sessions_dir()?;
+        
+        if !sessions_dir.exists() {
+            return Ok(vec![]);
+        }
+        
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&sessions_dir)?.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            
+            let name = match path.file_stem() {
+                Some(stem) => stem.to_string_lossy().to_string(),
+                None => continue,
+            };
+            let data = Self::read_session(&name).ok().flatten();
+            entries.push(SessionEntry { name, data });
+        }
+        
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+    
+    /// Remove a session and its action log. Returns false if it didn't exist.
+    /// The lock file stays: removing it would let a waiting command lock a file
+    /// no longer on disk while the next one creates and locks a new one.
+    pub fn delete_session(name: &str) -> Result<bool> {
+        let _lock = Self::lock_session(name)?;
+        if !Self::session_path(name)?.exists() {
+            return Ok(false);
+        }
+        
+        Self::remove_session_files(name)?;
+        Ok(true)
+    }
+    
+    /// Check a `--session` / `GUI_HELPER_SESSION` value. Names become file
+    /// names, so only letters, digits, `-` and `_` are allowed.
+    pub fn validate_session_name(name: &str) -> Result<String> {
+        let valid = !name.is_empty()
+            && name.len() <= 64
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        
+        if valid {
+            Ok(name.to_string())
+        } else {
+            Err(anyhow::anyhow!(
+                "Invalid session name '{}': use up to 64 letters, digits, '-' or '_'",
+                name
+            ))
+        }
+    }
+    
+    /// Take the exclusive lock for `name`, blocking until other writers finish.
+    pub fn lock_session(name: &str) -> Result<SessionLock> {
+        let lock_path = Self::session_path(name)?.with_extension("lock");
+        
+        if let Some(parent) = lock_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)?;
+        file.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock session '{}': {}", name, e))?;
+        
+        Ok(SessionLock { _file: file, name: name.to_string(), loaded: None })
+    }
+    
+    // Callers must hold the session lock
+    fn write_session(name: &str, session_data: &SessionData) -> Result<()> {
+        let session_path = Self::session_path(name)?;
+        
+        if let Some(parent) = session_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        
+        // Write to a temporary file first so readers never see a partial session
+        let json_data = serde_json::to_string_pretty(session_data)?;
+        let temp_path = session_path.with_extension("json.tmp");
+        fs::write(&temp_path, json_data)?;
+        fs::rename(&temp_path, &session_path)?;
+        Ok(())
     }
     
-    pub fn get_screenshots_dir() -> Result<PathBuf> {
+    fn sessions_dir() -> Result<PathBuf> {
         let data_dir = Config::data_dir()?;
-        let screenshots_dir = data_dir.join("screenshots");
+        Ok(data_dir.join("sessions"))
+    }
+    
+    fn actions_path(name: &str) -> Result<PathBuf> {
+        Ok(Self::sessions_dir()?.join(format!("{}.actions.jsonl", name)))
+    }
+    
+    fn session_path(name: &str) -> Result<PathBuf> {
+        Self::migrate_legacy_session()?;
+        Ok(Self::sessions_dir()?.join(format!("{}.json", name)))
+    }
+    
+    // Earlier versions kept a single `session.json` next to the sessions directory.
+    // If a default session exists too, the legacy file is kept as a backup.
+    fn migrate_legacy_session() -> Result<()> {
+        let legacy_path = Config::data_dir()?.join("session.json");
+        if !legacy_path.exists() {
+            return Ok(());
+        }
+        
+        let sessions_dir = Self::sessions_dir()?;
+        let default_path = sessions_dir.join(format!("{}.json", DEFAULT_SESSION));
+        
+        if default_path.exists() {
+            fs::rename(&legacy_path, legacy_path.with_extension("json.bak"))?;
+        } else {
+            fs::create_dir_all(&sessions_dir)?;
+            fs::rename(legacy_path, default_path)?;
+        }
+        
+        Ok(())
+    }
+    
+    pub fn get_screenshots_dir(config: &Config) -> Result<PathBuf> {
+        let screenshots_dir = config.screenshots_dir()?;
         
         if !screenshots_dir.exists() {
             fs::create_dir_all(&screenshots_dir)?;
@@ -75,35 +414,179 @@ impl SessionManager {
         Ok(screenshots_dir)
     }
     
-    pub fn generate_screenshot_filename(prefix: &str, format: &str) -> String {
-        let timestamp = Utc::now().format("%Y-%m-%d_%H-%M-%S");
-        format!("{}_{}.{}", prefix, timestamp, format)
+    pub fn generate_session_id() -> String {
+        Utc::now().format("%Y%m%d-%H%M%S-%3f").to_string()
     }
     
-    pub fn get_latest_screenshot() -> Result<Option<PathBuf>> {
-        let screenshots_dir = Self::get_screenshots_dir()?;
+    /// Create a new, empty file named `<prefix>_<time>.<format>`. The file is
+    /// created exclusively, so a name another process took first is skipped.
+    pub fn create_screenshot_file(screenshots_dir: &Path, prefix: &str, format: &str) -> Result<(PathBuf, fs::File)> {
+        // Millisecond resolution plus a counter, so two captures never share a name
+        let timestamp = Utc::now().format("%Y-%m-%d_%H-%M-%S-%3f");
+        let mut filename = format!("{}_{}.{}", prefix, timestamp, format);
+        let mut counter = 1;
         
-        if !screenshots_dir.exists() {
-            return Ok(None);
+        loop {
+            let path = screenshots_dir.join(&filename);
+            match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok((path, file)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    filename = format!("{}_{}-{}.{}", prefix, timestamp, counter, format);
+                    counter += 1;
+                }
+                Err(e) => return Err(anyhow::anyhow!("Failed to create {:?}: {}", path, e)),
+            }
         }
+    }
+    
+    /// Write a screenshot to `--output` name `custom_name`, replacing it, or to a
+    /// new file from `create_screenshot_file`. Returns the path written.
+    pub fn write_screenshot(screenshots_dir: &Path, custom_name: Option<&str>, prefix: &str, format: &str, bytes: &[u8]) -> Result<PathBuf> {
+        match custom_name {
+            Some(custom_name) => {
+                let output_path = screenshots_dir.join(custom_name);
+                fs::write(&output_path, bytes)?;
+                Ok(output_path)
+            }
+            None => {
+                let (output_path, mut file) = Self::create_screenshot_file(screenshots_dir, prefix, format)?;
+                file.write_all(bytes)?;
+                Ok(output_path)
+            }
+        }
+    }
+    
+    /// Where `--json` writes grid data: next to the screenshot, or under a name
+    /// of its own when the screenshot wasn't saved.
+    pub fn json_export_path(screenshots_dir: &Path, image_path: Option<&Path>, custom_name: Option<&str>, prefix: &str) -> Result<PathBuf> {
+        match (image_path, custom_name) {
+            (Some(image_path), _) => Ok(image_path.with_extension("json")),
+            (None, Some(custom_name)) => Ok(screenshots_dir.join(custom_name).with_extension("json")),
+            (None, None) => Ok(Self::create_screenshot_file(screenshots_dir, prefix, "json")?.0),
+        }
+    }
+    
+    /// Check a user-supplied `--output` name. Only plain file names are allowed,
+    /// so the screenshot always lands inside the screenshots directory.
+    pub fn sanitize_output_name(name: &str) -> Result<String> {
+        let mut components = Path::new(name).components();
+        
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(file_name)), None) => Ok(file_name.to_string_lossy().to_string()),
+            _ => Err(anyhow::anyhow!(
+                "Invalid output name '{}': use a plain file name without directories or '..'",
+                name
+            )),
+        }
+    }
+    
+    pub fn get_latest_screenshot(config: &Config) -> Result<Option<PathBuf>> {
+        ScreenshotStore::latest(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const UNVERSIONED_SESSION: &str = r#"{
+        "overview_grid": [],
+        "selected_square": "B2",
+        "zoom_area": null,
+        "zoom_grid": [],
+        "screen_info": {"width": 800, "height": 600, "grid_size": 100, "rows": 6, "cols": 8},
+        "timestamp": "2025-01-02T03:04:05.678Z"
+    }"#;
+    
+    #[test]
+    fn parse_session_migrates_unversioned_sessions() {
+        let session_data = SessionManager::parse_session(UNVERSIONED_SESSION).unwrap();
+        
+        assert_eq!(session_data.version, SESSION_VERSION);
+        assert_eq!(session_data.session_id.as_deref(), Some("20250102-030405-678"));
+        assert_eq!(session_data.history.len(), 1);
+        assert_eq!(session_data.history[0].command, "zoom B2");
+        assert_eq!(session_data.history[0].timestamp, session_data.timestamp);
+        assert_eq!(session_data.current_step, Some(0));
+    }
+    
+    #[test]
+    fn parse_session_keeps_current_sessions_as_they_are() {
+        let migrated = SessionManager::parse_session(UNVERSIONED_SESSION).unwrap();
+        let session_data = SessionManager::parse_session(&serde_json::to_string(&migrated).unwrap()).unwrap();
         
-        let mut entries: Vec<_> = fs::read_dir(&screenshots_dir)?
-            .filter_map(|entry| entry.ok())
-            .filter(|entry| {
-                entry.path().extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|ext| matches!(ext.to_lowercase().as_str(), "png" | "jpg" | "jpeg"))
-                    .unwrap_or(false)
+        assert_eq!(session_data.session_id, migrated.session_id);
+        assert_eq!(session_data.history.len(), 1);
+        assert_eq!(session_data.current_step, Some(0));
+    }
+    
+    #[test]
+    fn parse_session_refuses_newer_versions() {
+        let contents = UNVERSIONED_SESSION.replacen('{', &format!("{{\"version\": {},", SESSION_VERSION + 1), 1);
+        let error = SessionManager::parse_session(&contents).unwrap_err();
+        
+        assert!(error.is::<NewerVersion>());
+    }
+    
+    #[test]
+    fn parse_session_rejects_malformed_files() {
+        assert!(SessionManager::parse_session("").is_err());
+        assert!(SessionManager::parse_session("{\"version\": 1}").is_err());
+    }
+    
+    #[test]
+    fn accepts_plain_output_names() {
+        assert_eq!(SessionManager::sanitize_output_name("shot.png").unwrap(), "shot.png");
+        assert_eq!(SessionManager::sanitize_output_name("my shot.v2.jpg").unwrap(), "my shot.v2.jpg");
+    }
+    
+    #[test]
+    fn rejects_output_names_that_leave_the_directory() {
+        for name in ["", ".", "..", "../shot.png", "sub/shot.png", "/tmp/shot.png", "./shot.png", "shot.png/.."] {
+            assert!(SessionManager::sanitize_output_name(name).is_err(), "{:?}", name);
+        }
+    }
+    
+    #[test]
+    fn created_files_skip_taken_names() {
+        let dir = std::env::temp_dir().join(format!("gui-helper-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        
+        let mut names = Vec::new();
+        for _ in 0..3 {
+            let (path, mut file) = SessionManager::create_screenshot_file(&dir, "overview", "png").unwrap();
+            file.write_all(b"taken").unwrap();
+            names.push(path.file_name().unwrap().to_string_lossy().to_string());
+        }
+        
+        // Every file was created fresh, none overwrote another
+        for name in &names {
+            assert_eq!(fs::read(dir.join(name)).unwrap(), b"taken");
+        }
+        fs::remove_dir_all(&dir).unwrap();
+        
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), 3);
+        assert!(names.iter().all(|name| name.starts_with("overview_") && name.ends_with(".png")));
+    }
+    
+    #[test]
+    fn created_files_are_unique_across_threads() {
+        let dir = std::env::temp_dir().join(format!("gui-helper-test-threads-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let dir = dir.clone();
+                std::thread::spawn(move || SessionManager::create_screenshot_file(&dir, "zoom_B5", "png").unwrap().0)
             })
             .collect();
+        let mut paths: Vec<PathBuf> = threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+        fs::remove_dir_all(&dir).unwrap();
         
-        // Sort by modification time (newest first)
-        entries.sort_by(|a, b| {
-            let time_a = a.metadata().and_then(|m| m.modified()).unwrap_or(std::time::UNIX_EPOCH);
-            let time_b = b.metadata().and_then(|m| m.modified()).unwrap_or(std::time::UNIX_EPOCH);
-            time_b.cmp(&time_a)
-        });
-        
-        Ok(entries.first().map(|entry| entry.path()))
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths.len(), 8);
     }
-}
\ No newline at end of file
+}